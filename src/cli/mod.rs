@@ -0,0 +1,280 @@
+use clap::{Parser, Subcommand, ValueEnum};
+use std::io::BufRead;
+use std::path::PathBuf;
+use anyhow::Result;
+
+use crate::config::{Config, Layered};
+use crate::ipc::{self, Request};
+use crate::rofi;
+use crate::themes::{import, Theme};
+
+#[derive(Debug, Parser)]
+#[command(name = "5menu", version, about = "A modern application launcher")]
+pub struct Cli {
+    /// Stay resident in the background: start hidden and hide instead of exiting
+    #[arg(long)]
+    pub daemon: bool,
+
+    /// Stop an already running instance and take its place
+    #[arg(long)]
+    pub replace: bool,
+
+    /// Use this theme for this run; accepts a theme name or a path such as ./mine.toml
+    #[arg(long, value_name = "NAME|PATH")]
+    pub theme: Option<String>,
+
+    /// Label left of the input for this run
+    #[arg(long)]
+    pub prompt: Option<String>,
+
+    /// Text shown in the empty input for this run
+    #[arg(long)]
+    pub placeholder: Option<String>,
+
+    /// Message shown above the results for this run; may span several lines
+    #[arg(long, visible_alias = "mesg")]
+    pub message: Option<String>,
+
+    #[command(subcommand)]
+    pub command: Option<Commands>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Commands {
+    /// Send a command to the running instance
+    Msg {
+        #[command(subcommand)]
+        request: Request,
+    },
+    /// Print the effective configuration, or read and change single keys
+    Config {
+        /// Show which layer set each value
+        #[arg(long)]
+        explain: bool,
+
+        #[command(subcommand)]
+        action: Option<ConfigCommand>,
+    },
+    /// Manage themes
+    Theme {
+        #[command(subcommand)]
+        action: ThemeCommand,
+    },
+    /// Convert a rofi config.rasi or .rasi theme into a theme and config settings
+    ImportRofi {
+        /// Defaults to ~/.config/rofi/config.rasi
+        file: Option<PathBuf>,
+        /// Name of the theme to create
+        #[arg(short, long, default_value = "rofi")]
+        name: String,
+        /// Print what would be written without changing anything
+        #[arg(long)]
+        dry_run: bool,
+        /// Replace an existing theme file of the same name
+        #[arg(long)]
+        force: bool,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ThemeCommand {
+    /// Print a theme's TOML, e.g. to start a custom theme from a bundled one
+    Export {
+        name: String,
+        /// Write to a file instead of stdout
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Create a theme from pywal's colors.json, an Xresources file or a base16 scheme
+    Import {
+        /// Defaults to ~/.cache/wal/colors.json, or ~/.Xresources with --format xresources
+        file: Option<PathBuf>,
+        /// Guessed from the file extension when not given
+        #[arg(short, long, value_enum)]
+        format: Option<import::Format>,
+        /// Theme name; defaults to the scheme's name
+        #[arg(short, long)]
+        name: Option<String>,
+        /// Write here instead of ~/.config/5menu/themes/<name>.toml; `-` for stdout
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        /// Replace an existing theme file
+        #[arg(long)]
+        force: bool,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ConfigCommand {
+    /// Print the effective value of a key, e.g. `font_size`
+    Get { key: String },
+    /// Set a key in the user config file
+    Set { key: String, value: String },
+    /// Check a config file (or the full layered configuration) for errors
+    Validate { file: Option<PathBuf> },
+    /// Print a JSON Schema for editor completion and linting
+    Schema {
+        #[arg(value_enum, default_value_t = SchemaTarget::Config)]
+        target: SchemaTarget,
+    },
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum SchemaTarget {
+    Config,
+    Theme,
+}
+
+pub fn run_msg(mut request: Request) -> Result<()> {
+    if let Request::PushEntries { entries } = &mut request {
+        if entries.is_empty() {
+            *entries = std::io::stdin().lock().lines().collect::<Result<_, _>>()?;
+        }
+    }
+
+    let response = ipc::send(&request)?;
+    if !response.ok {
+        anyhow::bail!(response.error.unwrap_or_else(|| "Request failed".to_string()));
+    }
+    if let Request::PushEntries { .. } = request {
+        match response.selection {
+            Some(selection) => println!("{}", selection),
+            None => anyhow::bail!("Nothing was selected"),
+        }
+    }
+    Ok(())
+}
+
+pub fn run_config(explain: bool, action: Option<ConfigCommand>) -> Result<()> {
+    match action {
+        None => {
+            let layered = load_layered()?;
+            if explain {
+                print!("{}", layered.explain());
+            } else {
+                print!("{}", toml::to_string_pretty(&layered.config)?);
+            }
+        }
+        Some(ConfigCommand::Get { key }) => {
+            let layered = load_layered()?;
+            match layered.get(&key) {
+                Some(toml::Value::String(value)) => println!("{}", value),
+                Some(value) => println!("{}", value),
+                None => anyhow::bail!("unknown config key `{}`", key),
+            }
+        }
+        Some(ConfigCommand::Set { key, value }) => {
+            Config::set_user_value(&key, &value)?;
+        }
+        Some(ConfigCommand::Validate { file }) => {
+            let (name, warnings) = match file {
+                Some(file) => (file.display().to_string(), Config::validate_file(&file)?),
+                None => ("configuration".to_string(), Config::load_layered()?.warnings),
+            };
+            for warning in &warnings {
+                eprintln!("warning: {}", warning);
+            }
+            println!("{} is valid", name);
+        }
+        Some(ConfigCommand::Schema { target }) => {
+            let schema = match target {
+                SchemaTarget::Config => schemars::schema_for!(Config),
+                SchemaTarget::Theme => Theme::file_schema(),
+            };
+            println!("{}", serde_json::to_string_pretty(&schema)?);
+        }
+    }
+    Ok(())
+}
+
+fn load_layered() -> Result<Layered> {
+    let layered = Config::load_layered()?;
+    layered.print_warnings();
+    Ok(layered)
+}
+
+pub fn run_theme(action: ThemeCommand) -> Result<()> {
+    match action {
+        ThemeCommand::Export { name, output } => {
+            let theme_paths = Config::load().map(|c| c.theme_paths).unwrap_or_default();
+            let content = Theme::source(&name, &theme_paths)?;
+            match output {
+                Some(path) => std::fs::write(path, content)?,
+                None => print!("{}", content),
+            }
+        }
+        ThemeCommand::Import { file, format, name, output, force } => {
+            let format = format.unwrap_or_else(|| file.as_deref().map_or(import::Format::Pywal, import::Format::detect));
+            let Some(file) = file.or_else(|| format.default_path()) else {
+                anyhow::bail!("base16 schemes have no default location; pass the scheme file");
+            };
+            let content = import::import(format, &file, name.as_deref())?;
+
+            let path = match output {
+                Some(path) if path.as_os_str() == "-" => {
+                    print!("{}", content);
+                    return Ok(());
+                }
+                Some(path) => path,
+                None => {
+                    let table: toml::Table = toml::from_str(&content)?;
+                    let name = table.get("name").and_then(toml::Value::as_str).unwrap_or("imported");
+                    Theme::dir().join(format!("{}.toml", name))
+                }
+            };
+            if path.exists() && !force {
+                anyhow::bail!("{} already exists; pass --force to replace it", path.display());
+            }
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::write(&path, content)?;
+            println!("Wrote {}", path.display());
+        }
+    }
+    Ok(())
+}
+
+pub fn run_import_rofi(file: Option<PathBuf>, name: &str, dry_run: bool, force: bool) -> Result<()> {
+    let file = file.unwrap_or_else(rofi::default_config);
+    let import = rofi::import(&file, name)?;
+    let theme = toml::to_string(&import.theme)?;
+    let theme_file = Theme::dir().join(format!("{}.toml", name));
+
+    if dry_run {
+        println!("# {}", theme_file.display());
+        print!("{}", theme);
+        println!();
+        for (key, value) in &import.config {
+            println!("config: {} = {}", key, value);
+        }
+    } else {
+        if theme_file.exists() && !force {
+            anyhow::bail!("{} already exists; pass --force to replace it", theme_file.display());
+        }
+        if let Some(parent) = theme_file.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&theme_file, format!("# Imported from {}\n{}", file.display(), theme))?;
+        println!("Wrote {}", theme_file.display());
+
+        for (key, value) in import.config.iter().chain([&("theme".to_string(), name.to_string())]) {
+            Config::set_user_value(key, value)?;
+            println!("Set {} = {}", key, value);
+        }
+    }
+
+    if !import.approximated.is_empty() {
+        println!("\nApproximated:");
+        for note in &import.approximated {
+            println!("  {}", note);
+        }
+    }
+    if !import.skipped.is_empty() {
+        println!("\nNo equivalent in 5menu:");
+        for skipped in &import.skipped {
+            println!("  {}", skipped);
+        }
+    }
+    Ok(())
+}
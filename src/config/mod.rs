@@ -1,65 +1,1303 @@
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
-use anyhow::Result;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+use toml_edit::DocumentMut;
 
-#[derive(Debug, Serialize, Deserialize)]
+// Bump when a release renames or reinterprets keys, and add a step to `migrate`
+pub const CONFIG_VERSION: u32 = 1;
+
+#[derive(Debug, Error)]
+pub enum ConfigError {
+    #[error("failed to read {}: {source}", path.display())]
+    Read { path: PathBuf, source: std::io::Error },
+    #[error("failed to write {}: {source}", path.display())]
+    Write { path: PathBuf, source: std::io::Error },
+    #[error("{}:{line}:{column}: {message}", path.display())]
+    Syntax { path: PathBuf, line: usize, column: usize, message: String },
+    #[error("{}:{line}:{column}: invalid value for `{key}`: {message}", path.display())]
+    InvalidValue { path: PathBuf, key: String, line: usize, column: usize, message: String },
+    #[error("invalid value `{key} = {value}`: {message}")]
+    InvalidSetting { key: String, value: String, message: String },
+    #[error("`{var}`: cannot use `{value}` for this setting: {message}")]
+    InvalidEnv { var: String, value: String, message: String },
+    #[error("config files include each other: {}", .0.display())]
+    IncludeCycle(PathBuf),
+    #[error("unknown config key `{0}`")]
+    UnknownKey(String),
+    #[error("{0}")]
+    Merge(String),
+    #[error("failed to serialize config: {0}")]
+    Serialize(#[from] toml::ser::Error),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
 pub struct Config {
+    pub config_version: u32,
     pub theme: String,
-    pub width: u32,
-    pub height: u32,
+    // Shown in the empty input
+    pub placeholder: String,
+    // Shown above the results; may span several lines
+    pub message: String,
+    pub layout: Layout,
+    pub grid_columns: usize,
+    pub icon_size: u16,
+    pub preview: PreviewPosition,
+    // Run for the selected dmenu entry, with `{}` standing for it
+    pub preview_command: String,
+    pub width: Dimension,
+    pub height: Dimension,
+    pub location: Location,
+    pub x_offset: i32,
+    pub y_offset: i32,
+    pub monitor: MonitorSelector,
+    pub fullscreen: bool,
     pub font_size: u16,
     pub max_entries: usize,
+    // Shrink the list's window to the results, between `min_lines` and `max_lines` rows
+    pub auto_height: bool,
+    pub min_lines: usize,
+    pub max_lines: usize,
     pub terminal: String,
     pub search_paths: Vec<PathBuf>,
     pub hide_on_lose_focus: bool,
     pub case_sensitive: bool,
+    // Where the launcher's results come from, in the order they are listed
+    pub sources: Vec<Source>,
+    // Show each source's results under a header
+    pub group_results: bool,
+    pub theme_paths: Vec<PathBuf>,
+    pub prompts: Prompts,
+    pub source_limits: SourceLimits,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub include: Vec<PathBuf>,
 }
 
 impl Default for Config {
     fn default() -> Self {
         Self {
+            config_version: CONFIG_VERSION,
             theme: "default".to_string(),
-            width: 900,
-            height: 600,
+            placeholder: "Type to search...".to_string(),
+            message: String::new(),
+            layout: Layout::List,
+            grid_columns: 5,
+            icon_size: 48,
+            preview: PreviewPosition::Off,
+            preview_command: String::new(),
+            width: Dimension::Pixels(900),
+            height: Dimension::Pixels(600),
+            location: Location::Center,
+            x_offset: 0,
+            y_offset: 0,
+            monitor: MonitorSelector::Name("primary".to_string()),
+            fullscreen: false,
             font_size: 14,
             max_entries: 15,
+            auto_height: false,
+            min_lines: 1,
+            max_lines: 15,
             terminal: "xterm".to_string(),
             search_paths: vec!["/usr/bin".into(), "/usr/local/bin".into()],
             hide_on_lose_focus: true,
             case_sensitive: false,
+            // Settings results are opt-in, so ungrouped results stay as they were
+            sources: vec![Source::Calculator, Source::Apps],
+            group_results: false,
+            theme_paths: Vec::new(),
+            prompts: Prompts::default(),
+            source_limits: SourceLimits::default(),
+            include: Vec::new(),
+        }
+    }
+}
+
+// How results are arranged; `bar` is a single line across the top or bottom like dmenu,
+// `grid` shows tiles with icons
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum Layout {
+    List,
+    Bar,
+    Grid,
+}
+
+impl Layout {
+    pub const ALL: [Layout; 3] = [Layout::List, Layout::Bar, Layout::Grid];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Layout::List => "list",
+            Layout::Bar => "bar",
+            Layout::Grid => "grid",
+        }
+    }
+}
+
+// Label left of the input in each mode; empty for none. In settings it starts the breadcrumbs
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct Prompts {
+    pub apps: String,
+    pub settings: String,
+    pub dmenu: String,
+}
+
+impl Default for Prompts {
+    fn default() -> Self {
+        Self {
+            apps: String::new(),
+            settings: "Settings".to_string(),
+            dmenu: String::new(),
+        }
+    }
+}
+
+// A provider of results in apps mode
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum Source {
+    Calculator,
+    Apps,
+    Settings,
+}
+
+impl Source {
+    pub const ALL: [Source; 3] = [Source::Calculator, Source::Apps, Source::Settings];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Source::Calculator => "calculator",
+            Source::Apps => "apps",
+            Source::Settings => "settings",
+        }
+    }
+
+    // Header of the source's results
+    pub fn label(self) -> &'static str {
+        match self {
+            Source::Calculator => "Calculator",
+            Source::Apps => "Applications",
+            Source::Settings => "Settings",
+        }
+    }
+}
+
+// Most results each source adds; 0 for no limit besides `max_entries`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct SourceLimits {
+    pub calculator: usize,
+    pub apps: usize,
+    pub settings: usize,
+}
+
+impl Default for SourceLimits {
+    fn default() -> Self {
+        Self { calculator: 1, apps: 0, settings: 3 }
+    }
+}
+
+impl SourceLimits {
+    pub fn get(&self, source: Source) -> usize {
+        match source {
+            Source::Calculator => self.calculator,
+            Source::Apps => self.apps,
+            Source::Settings => self.settings,
+        }
+    }
+}
+
+// Where the pane previewing the selected entry goes, if anywhere
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum PreviewPosition {
+    Off,
+    Right,
+    Bottom,
+}
+
+impl PreviewPosition {
+    pub const ALL: [PreviewPosition; 3] = [PreviewPosition::Off, PreviewPosition::Right, PreviewPosition::Bottom];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            PreviewPosition::Off => "off",
+            PreviewPosition::Right => "right",
+            PreviewPosition::Bottom => "bottom",
+        }
+    }
+}
+
+// A window dimension in pixels, or relative to the monitor when written as "50%"
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "DimensionRepr", into = "DimensionRepr")]
+pub enum Dimension {
+    Pixels(u32),
+    Percent(u32),
+}
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+#[serde(untagged)]
+enum DimensionRepr {
+    Pixels(u32),
+    Text(String),
+}
+
+impl Dimension {
+    pub fn resolve(self, monitor: u32) -> u32 {
+        match self {
+            Dimension::Pixels(pixels) => pixels,
+            Dimension::Percent(percent) => monitor * percent / 100,
+        }
+    }
+}
+
+impl std::str::FromStr for Dimension {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        let s = s.trim();
+        match s.strip_suffix('%') {
+            Some(percent) => match percent.trim().parse() {
+                Ok(percent @ 1..=100) => Ok(Dimension::Percent(percent)),
+                _ => Err(format!("`{}` is not a percentage from 1% to 100%", s)),
+            },
+            None => s
+                .parse()
+                .map(Dimension::Pixels)
+                .map_err(|_| format!("`{}` is not a size in pixels or a percentage like \"50%\"", s)),
+        }
+    }
+}
+
+impl std::fmt::Display for Dimension {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Dimension::Pixels(pixels) => write!(f, "{}", pixels),
+            Dimension::Percent(percent) => write!(f, "{}%", percent),
+        }
+    }
+}
+
+impl TryFrom<DimensionRepr> for Dimension {
+    type Error = String;
+
+    fn try_from(repr: DimensionRepr) -> Result<Self, String> {
+        match repr {
+            DimensionRepr::Pixels(pixels) => Ok(Dimension::Pixels(pixels)),
+            DimensionRepr::Text(text) => text.parse(),
+        }
+    }
+}
+
+impl From<Dimension> for DimensionRepr {
+    fn from(dimension: Dimension) -> Self {
+        match dimension {
+            Dimension::Pixels(pixels) => DimensionRepr::Pixels(pixels),
+            percent => DimensionRepr::Text(percent.to_string()),
+        }
+    }
+}
+
+impl JsonSchema for Dimension {
+    fn schema_name() -> String {
+        "Dimension".to_string()
+    }
+
+    fn json_schema(gen: &mut schemars::gen::SchemaGenerator) -> schemars::schema::Schema {
+        DimensionRepr::json_schema(gen)
+    }
+}
+
+// Where on the monitor the window goes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum Location {
+    Center,
+    Top,
+    Bottom,
+    Left,
+    Right,
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+    AtCursor,
+}
+
+impl Location {
+    pub const ALL: [Location; 10] = [
+        Location::Center,
+        Location::Top,
+        Location::Bottom,
+        Location::Left,
+        Location::Right,
+        Location::TopLeft,
+        Location::TopRight,
+        Location::BottomLeft,
+        Location::BottomRight,
+        Location::AtCursor,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Location::Center => "center",
+            Location::Top => "top",
+            Location::Bottom => "bottom",
+            Location::Left => "left",
+            Location::Right => "right",
+            Location::TopLeft => "top-left",
+            Location::TopRight => "top-right",
+            Location::BottomLeft => "bottom-left",
+            Location::BottomRight => "bottom-right",
+            Location::AtCursor => "at-cursor",
+        }
+    }
+}
+
+// `"primary"`, `"focused"`, a monitor index from 0 or an output name such as `"HDMI-1"`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(untagged)]
+pub enum MonitorSelector {
+    Index(usize),
+    Name(String),
+}
+
+impl std::fmt::Display for MonitorSelector {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            MonitorSelector::Index(index) => write!(f, "{}", index),
+            MonitorSelector::Name(name) => f.write_str(name),
         }
     }
 }
 
 impl Config {
-    pub fn load() -> Result<Self> {
-        let config_dir = dirs::config_dir()
+    pub fn dir() -> PathBuf {
+        dirs::config_dir()
             .map(|d| d.join("5menu"))
-            .unwrap_or_else(|| PathBuf::from("config"));
-        
-        let config_file = config_dir.join("config.toml");
-        
-        if !config_file.exists() {
-            std::fs::create_dir_all(&config_dir)?;
-            let default_config = Self::default();
-            let toml = toml::to_string_pretty(&default_config)?;
-            std::fs::write(&config_file, toml)?;
-            return Ok(default_config);
+            .unwrap_or_else(|| PathBuf::from("config"))
+    }
+
+    pub fn path() -> PathBuf {
+        Self::dir().join("config.toml")
+    }
+
+    pub fn load() -> Result<Self, ConfigError> {
+        let layered = Self::load_layered()?;
+        layered.print_warnings();
+        Ok(layered.config)
+    }
+
+    // Merges, from lowest to highest precedence: built-in defaults, system files,
+    // the user file, any files it includes, and FMENU_* environment variables
+    pub fn load_layered() -> Result<Layered, ConfigError> {
+        Self::load_with(true)
+    }
+
+    // Like `load_layered`, but an invalid key, file or variable is left out and listed
+    // in `errors` rather than failing the whole configuration
+    pub fn load_lenient() -> Result<Layered, ConfigError> {
+        Self::load_with(false)
+    }
+
+    // Writes the default file for a first run; only the launcher calls this, so commands
+    // that just read the configuration never touch the disk. It only holds the version, so
+    // every other key keeps following the system files
+    pub fn ensure_user_file() -> Result<(), ConfigError> {
+        let config_file = Self::path();
+        if config_file.exists() {
+            return Ok(());
+        }
+        std::fs::create_dir_all(Self::dir())
+            .and_then(|_| std::fs::write(&config_file, format!("config_version = {}\n", CONFIG_VERSION)))
+            .map_err(|source| ConfigError::Write { path: config_file, source })
+    }
+
+    fn load_with(strict: bool) -> Result<Layered, ConfigError> {
+        let user_file = Self::path();
+        let mut layered = Layered::new(defaults_table(), "default");
+        layered.strict = strict;
+        for path in system_files() {
+            layered.merge_file(&path, false, &mut Vec::new())?;
+        }
+        layered.merge_file(&user_file, true, &mut Vec::new())?;
+        layered.merge_env()?;
+
+        layered.config = Self::from_table(layered.table.clone())?;
+        if let Err(message) = layered.config.validate() {
+            layered.skip(ConfigError::Merge(message))?;
+            layered.config.min_lines = layered.config.max_lines;
         }
+        Ok(layered)
+    }
 
-        let content = std::fs::read_to_string(config_file)?;
-        Ok(toml::from_str(&content)?)
+    fn from_table(table: toml::Table) -> Result<Self, ConfigError> {
+        toml::Value::Table(table)
+            .try_into()
+            .map_err(|e: toml::de::Error| ConfigError::Merge(e.message().to_string()))
     }
 
-    pub fn save(&self) -> Result<()> {
-        let config_dir = dirs::config_dir()
-            .map(|d| d.join("5menu"))
-            .unwrap_or_else(|| PathBuf::from("config"));
-        
-        std::fs::create_dir_all(&config_dir)?;
-        let config_file = config_dir.join("config.toml");
-        let toml = toml::to_string_pretty(&self)?;
-        std::fs::write(config_file, toml)?;
+    // Rules between keys, which checking each value on its own cannot catch. Layers are
+    // merged before this runs, so a file may set both keys or leave one to another layer
+    pub fn validate(&self) -> Result<(), String> {
+        if self.min_lines > self.max_lines {
+            return Err(format!(
+                "min_lines ({}) must not be more than max_lines ({})",
+                self.min_lines, self.max_lines
+            ));
+        }
+        Ok(())
+    }
+
+    // Reads a single file, migrating it in place when it was written by an older version
+    fn read_layer(path: &Path, in_place: bool) -> Result<Layer, ConfigError> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|source| ConfigError::Read { path: path.to_path_buf(), source })?;
+        let mut document: DocumentMut = content
+            .parse()
+            .map_err(|e: toml_edit::TomlError| syntax_error(path, &content, e.span(), e.message()))?;
+
+        let version = document
+            .get("config_version")
+            .and_then(toml_edit::Item::as_integer)
+            .map(|v| v as u32);
+        let from = version.unwrap_or(0);
+        if from < CONFIG_VERSION {
+            // Steps edit the document, so the file keeps its comments and layout
+            migrate(&mut document, from);
+
+            if in_place {
+                let backup = path.with_extension("toml.bak");
+                log::info!("Migrating {} to version {} (backup in {})", path.display(), CONFIG_VERSION, backup.display());
+                std::fs::copy(path, &backup)
+                    .and_then(|_| std::fs::write(path, document.to_string()))
+                    .map_err(|source| ConfigError::Write { path: path.to_path_buf(), source })?;
+            } else if version.is_none() {
+                // Fragments and system files only set what they mention
+                document.remove("config_version");
+            }
+        }
+
+        let migrated = document.to_string();
+        let mut table: toml::Table = toml::from_str(&migrated)
+            .map_err(|e| syntax_error(path, &migrated, e.span(), e.message()))?;
+
+        // Each key is checked on its own and taken out when invalid, so a lenient load
+        // loses only that key. Positions are looked up as written on disk
+        let mut invalid = Vec::new();
+        for (key, value) in flatten(&table) {
+            let key_path: Vec<&str> = key.split('.').collect();
+            if let Err(message) = check_value(&key_path, &value) {
+                remove(&mut table, &key_path);
+                let (line, column) = line_column(&content, value_offset(&content, &key_path).unwrap_or(0));
+                invalid.push(ConfigError::InvalidValue { path: path.to_path_buf(), key, line, column, message });
+            }
+        }
+
+        let mut warnings = unknown_keys(path, &table);
+        if from > CONFIG_VERSION {
+            warnings.insert(0, format!(
+                "{}: config_version {} is newer than this build supports ({})",
+                path.display(), from, CONFIG_VERSION
+            ));
+        }
+        Ok(Layer { table, warnings, invalid })
+    }
+
+    // Checks a single file on its own, without migrating it
+    pub fn validate_file(path: &Path) -> Result<Vec<String>, ConfigError> {
+        let layer = Self::read_layer(path, false)?;
+        match layer.invalid.into_iter().next() {
+            Some(error) => Err(error),
+            None => Ok(layer.warnings),
+        }
+    }
+
+    // Sets a dotted key in the user file, checking the value against the key's type
+    pub fn set_user_value(key: &str, raw: &str) -> Result<(), ConfigError> {
+        let value = Self::setting(key, raw)?;
+
+        // Also checked against the rest of the configuration, e.g. `min_lines` against `max_lines`
+        let mut table = Self::load_lenient()?.table;
+        insert(&mut table, &key.split('.').collect::<Vec<_>>(), value.clone());
+        Self::from_table(table)?.validate().map_err(|message| ConfigError::InvalidSetting {
+            key: key.to_string(),
+            value: value.to_string(),
+            message,
+        })?;
+        Self::ensure_user_file()?;
+        write_setting(&Self::path(), key, value)
+    }
+
+    // The value `config set` stores for `raw`, held to the same limits as the settings screen
+    fn setting(key: &str, raw: &str) -> Result<toml::Value, ConfigError> {
+        let path: Vec<String> = key.split('.').map(str::to_string).collect();
+        let defaults = defaults_table();
+        let default = lookup(&defaults, &path).ok_or_else(|| ConfigError::UnknownKey(key.to_string()))?;
+
+        // Accept TOML syntax (`16`, `true`, `["/a", "/b"]`) and fall back to a plain string
+        let value = toml::from_str::<toml::Table>(&format!("value = {}", raw))
+            .ok()
+            .and_then(|mut t| t.remove("value"))
+            .filter(|v| v.same_type(default))
+            .or_else(|| env_value(default, raw))
+            .unwrap_or_else(|| toml::Value::String(raw.to_string()));
+
+        // Checked on its own, so the error is about this value rather than a line of a
+        // file that was never written
+        check_value(&path, &value).map_err(|message| ConfigError::InvalidSetting {
+            key: key.to_string(),
+            value: value.to_string(),
+            message,
+        })?;
+        Ok(value)
+    }
+
+    // Writes this config's value for one key to the user file. Only the key that was
+    // changed is written, so values that came from other layers or FMENU_* variables stay
+    // where they are, and the rest of the file keeps its comments and layout
+    pub fn save_key(&self, key: &str) -> Result<(), ConfigError> {
+        Self::ensure_user_file()?;
+        self.write_key(&Self::path(), key)
+    }
+
+    fn write_key(&self, file: &Path, key: &str) -> Result<(), ConfigError> {
+        let table = toml::Table::try_from(self)?;
+        let path: Vec<&str> = key.split('.').collect();
+        let value = lookup(&table, &path).ok_or_else(|| ConfigError::UnknownKey(key.to_string()))?;
+        write_setting(file, key, value.clone())
+    }
+}
+
+// One file's keys, less any that failed their check
+struct Layer {
+    table: toml::Table,
+    warnings: Vec<String>,
+    invalid: Vec<ConfigError>,
+}
+
+pub struct Layered {
+    pub config: Config,
+    pub warnings: Vec<String>,
+    // Keys, files and variables left out for being invalid; always empty when loaded strictly
+    pub errors: Vec<String>,
+    strict: bool,
+    table: toml::Table,
+    // Dotted key -> the layer that last set it
+    sources: BTreeMap<String, String>,
+}
+
+impl Layered {
+    // Printed rather than logged, since the default log filter only shows errors
+    pub fn print_warnings(&self) {
+        for warning in &self.warnings {
+            eprintln!("warning: {}", warning);
+        }
+        for error in &self.errors {
+            eprintln!("error: {} (skipped)", error);
+        }
+    }
+
+    fn new(table: toml::Table, source: &str) -> Self {
+        let mut sources = BTreeMap::new();
+        for key in flatten(&table) {
+            sources.insert(key.0, source.to_string());
+        }
+        Self {
+            config: Config::default(),
+            warnings: Vec::new(),
+            errors: Vec::new(),
+            strict: true,
+            table,
+            sources,
+        }
+    }
+
+    // Fails a strict load, and otherwise notes the error so the rest can still load
+    fn skip(&mut self, error: ConfigError) -> Result<(), ConfigError> {
+        if self.strict {
+            return Err(error);
+        }
+        self.errors.push(error.to_string());
         Ok(())
     }
+
+    fn merge(&mut self, table: toml::Table, source: &str) {
+        for (key, _) in flatten(&table) {
+            self.sources.insert(key, source.to_string());
+        }
+        merge_tables(&mut self.table, table);
+    }
+
+    fn merge_file(&mut self, path: &Path, in_place: bool, stack: &mut Vec<PathBuf>) -> Result<(), ConfigError> {
+        if !path.exists() {
+            return Ok(());
+        }
+        let canonical = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        if stack.contains(&canonical) {
+            return self.skip(ConfigError::IncludeCycle(canonical));
+        }
+
+        let Layer { mut table, warnings, invalid } = match Config::read_layer(path, in_place) {
+            Ok(layer) => layer,
+            Err(e) => return self.skip(e),
+        };
+        self.warnings.extend(warnings);
+        for error in invalid {
+            self.skip(error)?;
+        }
+
+        let includes = match table.remove("include").map(toml::Value::try_into::<Vec<PathBuf>>) {
+            Some(Ok(includes)) => includes,
+            Some(Err(e)) => {
+                self.skip(ConfigError::Merge(format!("{}: invalid include list: {}", path.display(), e.message())))?;
+                Vec::new()
+            }
+            None => Vec::new(),
+        };
+        self.merge(table, &path.display().to_string());
+
+        // Included fragments override the file that includes them
+        stack.push(canonical);
+        let base = path.parent().unwrap_or(Path::new("."));
+        for include in includes {
+            let include = base.join(expand_home(&include));
+            if !include.exists() {
+                self.warnings.push(format!("{}: included file {} not found", path.display(), include.display()));
+            }
+            self.merge_file(&include, false, stack)?;
+        }
+        stack.pop();
+        Ok(())
+    }
+
+    fn merge_env(&mut self) -> Result<(), ConfigError> {
+        let defaults = defaults_table();
+        let mut vars: Vec<_> = std::env::vars().filter(|(k, _)| k.starts_with(ENV_PREFIX)).collect();
+        vars.sort();
+
+        for (var, raw) in vars {
+            // FMENU_FONT_SIZE -> font_size, FMENU_SECTION__KEY -> section.key
+            let path: Vec<String> = var[ENV_PREFIX.len()..]
+                .to_lowercase()
+                .split("__")
+                .map(str::to_string)
+                .collect();
+
+            let Some(default) = lookup(&defaults, &path) else {
+                self.warnings.push(format!("{}: no config key `{}`", var, path.join(".")));
+                continue;
+            };
+            // Checked one at a time, so a bad variable costs only its own key
+            let checked = env_value(default, &raw)
+                .ok_or_else(|| format!("expected {}", default.type_str()))
+                .and_then(|value| check_value(&path, &value).map(|()| value));
+            let value = match checked {
+                Ok(value) => value,
+                Err(message) => {
+                    self.skip(ConfigError::InvalidEnv { var: var.clone(), value: raw.clone(), message })?;
+                    continue;
+                }
+            };
+
+            let mut table = toml::Table::new();
+            insert(&mut table, &path, value);
+            self.merge(table, &format!("${}", var));
+        }
+        Ok(())
+    }
+
+    pub fn get(&self, key: &str) -> Option<&toml::Value> {
+        let path: Vec<String> = key.split('.').map(str::to_string).collect();
+        lookup(&self.table, &path)
+    }
+
+    // One `key = value  # layer` line per effective setting
+    pub fn explain(&self) -> String {
+        let leaves = flatten(&self.table);
+        let width = leaves.iter().map(|(k, v)| k.len() + v.to_string().len()).max().unwrap_or(0);
+
+        leaves
+            .into_iter()
+            .map(|(key, value)| {
+                let value = value.to_string();
+                let source = self.sources.get(&key).map(String::as_str).unwrap_or("default");
+                let pad = width - key.len() - value.len();
+                format!("{} = {}{}  # {}\n", key, value, " ".repeat(pad), source)
+            })
+            .collect()
+    }
+}
+
+const ENV_PREFIX: &str = "FMENU_";
+
+// Bounds of the numeric settings, for both the settings screen and `config set`. Sizes given
+// as a percentage of the monitor are not held to these
+const RANGES: &[(&str, i64, i64)] = &[
+    ("grid_columns", 1, 12),
+    ("icon_size", 16, 256),
+    ("width", 200, 7680),
+    ("height", 100, 4320),
+    ("x_offset", -7680, 7680),
+    ("y_offset", -4320, 4320),
+    ("font_size", 6, 72),
+    ("max_entries", 1, 100),
+    ("min_lines", 0, 100),
+    ("max_lines", 1, 100),
+    ("source_limits.calculator", 0, 100),
+    ("source_limits.apps", 0, 100),
+    ("source_limits.settings", 0, 100),
+];
+
+pub fn range(key: &str) -> Option<(i64, i64)> {
+    RANGES.iter().find(|(k, ..)| *k == key).map(|&(_, min, max)| (min, max))
+}
+
+// Checks a value for the key at `path` on its own, against the key's type and its range
+fn check_value<S: AsRef<str>>(path: &[S], value: &toml::Value) -> Result<(), String> {
+    let mut check = defaults_table();
+    insert(&mut check, path, value.clone());
+    serde_path_to_error::deserialize::<_, Config>(toml::Value::Table(check))
+        .map_err(|e| e.into_inner().message().to_string())?;
+
+    let key = path.iter().map(AsRef::as_ref).collect::<Vec<_>>().join(".");
+    match (range(&key), value) {
+        (Some((min, max)), toml::Value::Integer(number)) if !(min..=max).contains(number) => {
+            Err(format!("must be between {} and {}", min, max))
+        }
+        _ => Ok(()),
+    }
+}
+
+fn defaults_table() -> toml::Table {
+    toml::Table::try_from(Config::default()).expect("default config serializes")
+}
+
+// /etc/xdg first, then $XDG_CONFIG_DIRS from least to most important
+fn system_files() -> Vec<PathBuf> {
+    let mut dirs = vec![PathBuf::from("/etc/xdg")];
+    if let Ok(value) = std::env::var("XDG_CONFIG_DIRS") {
+        for dir in std::env::split_paths(&value).collect::<Vec<_>>().into_iter().rev() {
+            if dir.is_absolute() && !dirs.contains(&dir) {
+                dirs.push(dir);
+            }
+        }
+    }
+    dirs.into_iter().map(|d| d.join("5menu").join("config.toml")).collect()
+}
+
+pub fn expand_home(path: &Path) -> PathBuf {
+    match (path.strip_prefix("~"), dirs::home_dir()) {
+        (Ok(rest), Some(home)) => home.join(rest),
+        _ => path.to_path_buf(),
+    }
+}
+
+pub fn merge_tables(base: &mut toml::Table, overlay: toml::Table) {
+    for (key, value) in overlay {
+        match (base.get_mut(&key), value) {
+            (Some(toml::Value::Table(base)), toml::Value::Table(overlay)) => merge_tables(base, overlay),
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+pub fn flatten(table: &toml::Table) -> Vec<(String, toml::Value)> {
+    let mut leaves = Vec::new();
+    for (key, value) in table {
+        match value {
+            toml::Value::Table(inner) => {
+                for (sub, value) in flatten(inner) {
+                    leaves.push((format!("{}.{}", key, sub), value));
+                }
+            }
+            value => leaves.push((key.clone(), value.clone())),
+        }
+    }
+    leaves
+}
+
+// The value at a key path such as `["source_limits", "apps"]`
+pub fn lookup<'a, S: AsRef<str>>(table: &'a toml::Table, path: &[S]) -> Option<&'a toml::Value> {
+    let (first, rest) = path.split_first()?;
+    let value = table.get(first.as_ref())?;
+    match (rest.is_empty(), value) {
+        (true, value) => Some(value),
+        (false, toml::Value::Table(inner)) => lookup(inner, rest),
+        _ => None,
+    }
+}
+
+// Takes out the value at a key path, if there is one
+fn remove<S: AsRef<str>>(table: &mut toml::Table, path: &[S]) {
+    let Some((leaf, parents)) = path.split_last() else {
+        return;
+    };
+    let mut cursor = table;
+    for parent in parents {
+        match cursor.get_mut(parent.as_ref()) {
+            Some(toml::Value::Table(inner)) => cursor = inner,
+            _ => return,
+        }
+    }
+    cursor.remove(leaf.as_ref());
+}
+
+// Sets the value at a key path, creating the tables on the way, or replacing values that
+// are in the way
+pub fn insert<S: AsRef<str>>(table: &mut toml::Table, path: &[S], value: toml::Value) {
+    let (leaf, parents) = path.split_last().expect("key paths are never empty");
+    let mut cursor = table;
+    for parent in parents {
+        let entry = cursor
+            .entry(parent.as_ref())
+            .or_insert_with(|| toml::Value::Table(toml::Table::new()));
+        if !entry.is_table() {
+            *entry = toml::Value::Table(toml::Table::new());
+        }
+        cursor = entry.as_table_mut().expect("just ensured a table");
+    }
+    cursor.insert(leaf.as_ref().to_string(), value);
+}
+
+// Edits the one value in place, so the rest of the file keeps its comments and layout
+fn write_setting(file: &Path, key: &str, value: toml::Value) -> Result<(), ConfigError> {
+    let content = std::fs::read_to_string(file)
+        .map_err(|source| ConfigError::Read { path: file.to_path_buf(), source })?;
+    let mut document: DocumentMut = content
+        .parse()
+        .map_err(|e: toml_edit::TomlError| syntax_error(file, &content, e.span(), e.message()))?;
+    let value: toml_edit::Value = value.to_string().parse().expect("TOML values print as TOML");
+    let path: Vec<&str> = key.split('.').collect();
+    insert_edit(&mut document, &path, value);
+
+    std::fs::write(file, document.to_string())
+        .map_err(|source| ConfigError::Write { path: file.to_path_buf(), source })
+}
+
+// Like `insert`, but in a document, keeping the comments and layout around the value
+fn insert_edit<S: AsRef<str>>(document: &mut DocumentMut, path: &[S], mut value: toml_edit::Value) {
+    let (leaf, parents) = path.split_last().expect("key paths are never empty");
+    let mut cursor: &mut dyn toml_edit::TableLike = document.as_table_mut();
+    for parent in parents {
+        let entry = cursor.entry(parent.as_ref()).or_insert_with(toml_edit::table);
+        if !entry.is_table_like() {
+            *entry = toml_edit::table();
+        }
+        cursor = entry.as_table_like_mut().expect("just ensured a table");
+    }
+    match cursor.get_mut(leaf.as_ref()) {
+        // Replacing the item rather than the entry keeps comments above the key, and the
+        // old value's decor keeps a comment after it
+        Some(item) => {
+            if let Some(old) = item.as_value() {
+                *value.decor_mut() = old.decor().clone();
+            }
+            *item = toml_edit::Item::Value(value);
+        }
+        None => {
+            cursor.insert(leaf.as_ref(), toml_edit::Item::Value(value));
+        }
+    }
+}
+
+// Environment values are plain strings, so use the default's type to interpret them
+fn env_value(default: &toml::Value, raw: &str) -> Option<toml::Value> {
+    match default {
+        toml::Value::String(_) => Some(toml::Value::String(raw.to_string())),
+        // Sizes also take a percentage of the monitor
+        toml::Value::Integer(_) if raw.trim().ends_with('%') => Some(toml::Value::String(raw.trim().to_string())),
+        toml::Value::Integer(_) => raw.trim().parse().ok().map(toml::Value::Integer),
+        toml::Value::Float(_) => raw.trim().parse().ok().map(toml::Value::Float),
+        toml::Value::Boolean(_) => match raw.trim().to_lowercase().as_str() {
+            "1" | "true" | "yes" | "on" => Some(toml::Value::Boolean(true)),
+            "0" | "false" | "no" | "off" => Some(toml::Value::Boolean(false)),
+            _ => None,
+        },
+        toml::Value::Array(_) => Some(toml::Value::Array(
+            std::env::split_paths(raw)
+                .filter(|p| !p.as_os_str().is_empty())
+                .map(|p| toml::Value::String(p.to_string_lossy().into_owned()))
+                .collect(),
+        )),
+        _ => None,
+    }
+}
+
+fn migrate(document: &mut DocumentMut, from: u32) {
+    // Version 0 files predate `config_version`; their keys are unchanged in version 1
+    if from < 1 {
+        document.insert("config_version", toml_edit::value(1));
+    }
+}
+
+// Where the value at a key path starts in the file's text
+fn value_offset<S: AsRef<str>>(content: &str, path: &[S]) -> Option<usize> {
+    let document = toml_edit::ImDocument::parse(content).ok()?;
+    let (leaf, parents) = path.split_last()?;
+    let mut table: &dyn toml_edit::TableLike = document.as_table();
+    for parent in parents {
+        table = table.get(parent.as_ref())?.as_table_like()?;
+    }
+    table.get(leaf.as_ref())?.span().map(|span| span.start)
+}
+
+fn unknown_keys(path: &Path, table: &toml::Table) -> Vec<String> {
+    let mut unknown = Vec::new();
+    let mut track = |key: serde_ignored::Path| unknown.push(key.to_string());
+    let deserializer = serde_ignored::Deserializer::new(toml::Value::Table(table.clone()), &mut track);
+    let _ = Config::deserialize(deserializer);
+    unknown
+        .into_iter()
+        .map(|key| format!("{}: unknown key `{}` ignored", path.display(), key))
+        .collect()
+}
+
+fn syntax_error(path: &Path, content: &str, span: Option<std::ops::Range<usize>>, message: &str) -> ConfigError {
+    let (line, column) = line_column(content, span.map_or(0, |span| span.start));
+    ConfigError::Syntax {
+        path: path.to_path_buf(),
+        line,
+        column,
+        message: message.to_string(),
+    }
+}
+
+pub fn line_column(content: &str, offset: usize) -> (usize, usize) {
+    let before = &content[..offset.min(content.len())];
+    let line = before.matches('\n').count() + 1;
+    let column = before.chars().rev().take_while(|&c| c != '\n').count() + 1;
+    (line, column)
+}
+
+// Temporary directories for tests across the crate
+#[cfg(test)]
+pub mod scratch {
+    use std::path::{Path, PathBuf};
+
+    // Removed with everything in it when dropped, so a failing assert still cleans up
+    pub struct ScratchDir(PathBuf);
+
+    impl ScratchDir {
+        // `name` must be unique across the crate's tests, since they run in parallel
+        pub fn new(name: &str) -> Self {
+            let dir = std::env::temp_dir().join(format!("5menu-test-{}-{}", name, std::process::id()));
+            let _ = std::fs::remove_dir_all(&dir);
+            std::fs::create_dir_all(&dir).unwrap();
+            Self(dir)
+        }
+
+        pub fn write(&self, file: &str, content: &str) -> PathBuf {
+            let path = self.0.join(file);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(&path, content).unwrap();
+            path
+        }
+    }
+
+    impl std::ops::Deref for ScratchDir {
+        type Target = Path;
+
+        fn deref(&self) -> &Path {
+            &self.0
+        }
+    }
+
+    impl Drop for ScratchDir {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::scratch::ScratchDir;
+    use super::*;
+
+    // Tests that set FMENU_* variables hold this, since every load reads all of them
+    static ENV: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
+    fn lenient() -> Layered {
+        let mut layered = Layered::new(defaults_table(), "default");
+        layered.strict = false;
+        layered
+    }
+
+    #[test]
+    fn lenient_loads_skip_only_the_bad_key() {
+        let good_dir = ScratchDir::new("config-good-layer");
+        let good = good_dir.write("config.toml", "font_size = 20\n");
+        let bad_dir = ScratchDir::new("config-bad-layer");
+        let bad = bad_dir.write("config.toml", "theme = \"ocean\"\nmax_entries = \"many\"\n\n[prompts]\napps = 3\n");
+        let mut layered = lenient();
+        layered.merge_file(&good, false, &mut Vec::new()).unwrap();
+        layered.merge_file(&bad, false, &mut Vec::new()).unwrap();
+        let mut strict = Layered::new(defaults_table(), "default");
+        let error = strict.merge_file(&bad, false, &mut Vec::new());
+
+        assert_eq!(layered.get("font_size"), Some(&toml::Value::Integer(20)));
+        assert_eq!(layered.get("theme"), Some(&toml::Value::String("ocean".to_string())));
+        assert_eq!(layered.get("max_entries"), Some(&toml::Value::Integer(15)));
+        assert_eq!(layered.get("prompts.apps"), Some(&toml::Value::String(String::new())));
+        assert_eq!(layered.errors.len(), 2, "{:?}", layered.errors);
+        assert!(layered.errors[0].contains(":2:15: invalid value for `max_entries`"), "{}", layered.errors[0]);
+        assert!(layered.errors[1].contains(":5:8: invalid value for `prompts.apps`"), "{}", layered.errors[1]);
+        assert!(matches!(error, Err(ConfigError::InvalidValue { .. })));
+    }
+
+    #[test]
+    fn lenient_loads_skip_only_the_bad_variable() {
+        let _env = ENV.lock().unwrap();
+        std::env::set_var("FMENU_LAYOUT", "sideways");
+        std::env::set_var("FMENU_MAX_ENTRIES", "0");
+        std::env::set_var("FMENU_FONT_SIZE", "20");
+        let mut layered = lenient();
+        layered.merge_env().unwrap();
+        let strict = Layered::new(defaults_table(), "default").merge_env();
+        for var in ["FMENU_LAYOUT", "FMENU_MAX_ENTRIES", "FMENU_FONT_SIZE"] {
+            std::env::remove_var(var);
+        }
+
+        assert_eq!(layered.get("font_size"), Some(&toml::Value::Integer(20)));
+        assert_eq!(layered.get("layout"), Some(&toml::Value::String("list".to_string())));
+        assert_eq!(layered.get("max_entries"), Some(&toml::Value::Integer(15)));
+        assert_eq!(layered.errors.len(), 2, "{:?}", layered.errors);
+        assert!(matches!(strict, Err(ConfigError::InvalidEnv { .. })));
+    }
+
+    #[test]
+    fn set_keeps_comments() {
+        let content = "# my settings\nfont_size = 14  # readable\ntheme = \"ocean\"\n\n[prompts]\napps = \"run\"\n";
+        let dir = ScratchDir::new("config-set");
+        let path = dir.write("config.toml", content);
+        write_setting(&path, "font_size", Config::setting("font_size", "16").unwrap()).unwrap();
+        write_setting(&path, "source_limits.apps", Config::setting("source_limits.apps", "5").unwrap()).unwrap();
+        write_setting(&path, "prompts.apps", Config::setting("prompts.apps", "go").unwrap()).unwrap();
+        let written = std::fs::read_to_string(&path).unwrap();
+
+        assert_eq!(
+            written,
+            "# my settings\nfont_size = 16  # readable\ntheme = \"ocean\"\n\n[prompts]\napps = \"go\"\n\n\
+             [source_limits]\napps = 5\n"
+        );
+    }
+
+    #[test]
+    fn saving_writes_only_the_changed_key() {
+        let _env = ENV.lock().unwrap();
+        let dir = ScratchDir::new("config-save-key");
+        let path = dir.write("config.toml", "config_version = 1\n# mine\nfont_size = 12\n");
+        std::env::set_var("FMENU_THEME", "ocean");
+        let mut layered = Layered::new(defaults_table(), "default");
+        layered.merge_file(&path, false, &mut Vec::new()).unwrap();
+        let merged = layered.merge_env();
+        std::env::remove_var("FMENU_THEME");
+        merged.unwrap();
+
+        let mut config: Config = toml::Value::Table(layered.table).try_into().unwrap();
+        assert_eq!(config.theme, "ocean");
+        config.max_entries = 20;
+        config.write_key(&path, "max_entries").unwrap();
+
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "config_version = 1\n# mine\nfont_size = 12\nmax_entries = 20\n"
+        );
+    }
+
+    #[test]
+    fn set_holds_values_to_the_settings_limits() {
+        assert!(matches!(Config::setting("max_entries", "0"), Err(ConfigError::InvalidSetting { .. })));
+        assert!(matches!(Config::setting("font_size", "200"), Err(ConfigError::InvalidSetting { .. })));
+        assert!(matches!(Config::setting("width", "50"), Err(ConfigError::InvalidSetting { .. })));
+        assert_eq!(Config::setting("width", "50%").unwrap(), toml::Value::String("50%".to_string()));
+        assert_eq!(Config::setting("max_entries", "1").unwrap(), toml::Value::Integer(1));
+        assert!(matches!(Config::setting("nope", "1"), Err(ConfigError::UnknownKey(_))));
+    }
+
+    #[test]
+    fn reports_errors_at_the_line_on_disk() {
+        let dir = ScratchDir::new("config-span");
+        let path = dir.write("config.toml", "# A fragment\n\ntheme = \"ocean\"\n\n\n\n\n\n\nfont_size = \"big\"\n");
+        let error = Config::validate_file(&path).unwrap_err();
+
+        assert!(
+            matches!(error, ConfigError::InvalidValue { line: 10, column: 13, ref key, .. } if key == "font_size"),
+            "{}", error
+        );
+    }
+
+    #[test]
+    fn migration_only_adds_the_version() {
+        let content = "# my settings\ntheme = \"ocean\"  # the blue one\n\n[prompts]\napps = \"run\"\n";
+        let dir = ScratchDir::new("config-migrate");
+        let path = dir.write("config.toml", content);
+        let table = Config::read_layer(&path, true).unwrap().table;
+        let written = std::fs::read_to_string(&path).unwrap();
+        let backup = std::fs::read_to_string(path.with_extension("toml.bak")).unwrap();
+
+        assert_eq!(table.get("config_version"), Some(&toml::Value::Integer(1)));
+        assert_eq!(backup, content);
+        assert_eq!(
+            written,
+            "# my settings\ntheme = \"ocean\"  # the blue one\nconfig_version = 1\n\n[prompts]\napps = \"run\"\n"
+        );
+    }
+
+    #[test]
+    fn migrate_steps_from_each_version() {
+        let mut document: DocumentMut = "theme = \"ocean\"\n".parse().unwrap();
+        migrate(&mut document, 0);
+        assert_eq!(document.to_string(), "theme = \"ocean\"\nconfig_version = 1\n");
+
+        let mut current: DocumentMut = "config_version = 1\ntheme = \"ocean\"\n".parse().unwrap();
+        migrate(&mut current, CONFIG_VERSION);
+        assert_eq!(current.to_string(), "config_version = 1\ntheme = \"ocean\"\n");
+    }
+
+    #[test]
+    fn current_files_are_left_alone() {
+        let content = "config_version = 1\n# mine\ntheme = \"ocean\"\n";
+        let dir = ScratchDir::new("config-current");
+        let path = dir.write("config.toml", content);
+        Config::read_layer(&path, true).unwrap();
+        let written = std::fs::read_to_string(&path).unwrap();
+        let backup = path.with_extension("toml.bak").exists();
+
+        assert_eq!(written, content);
+        assert!(!backup);
+    }
+
+    #[test]
+    fn newer_files_are_read_with_a_warning() {
+        let dir = ScratchDir::new("config-newer");
+        let path = dir.write("config.toml", "config_version = 99\nfont_size = 20\n");
+        let Layer { table, warnings, .. } = Config::read_layer(&path, true).unwrap();
+
+        assert_eq!(table.get("font_size"), Some(&toml::Value::Integer(20)));
+        assert!(warnings[0].contains("config_version 99 is newer"), "{:?}", warnings);
+    }
+
+    #[test]
+    fn fragments_are_not_given_a_version() {
+        let dir = ScratchDir::new("config-fragment");
+        let path = dir.write("config.toml", "theme = \"ocean\"\n");
+        let table = Config::read_layer(&path, false).unwrap().table;
+        let written = std::fs::read_to_string(&path).unwrap();
+
+        assert!(!table.contains_key("config_version"));
+        assert_eq!(written, "theme = \"ocean\"\n");
+    }
+
+    fn string(value: &str) -> toml::Value {
+        toml::Value::String(value.to_string())
+    }
+
+    #[test]
+    fn later_layers_win_key_by_key() {
+        let dir = ScratchDir::new("config-precedence");
+        let system = dir.join("system.toml");
+        let user = dir.join("user.toml");
+        std::fs::write(&system, "theme = \"ocean\"\nfont_size = 12\n\n[prompts]\napps = \"run\"\n").unwrap();
+        std::fs::write(&user, "font_size = 18\n\n[prompts]\ndmenu = \"pick\"\n").unwrap();
+        let mut layered = Layered::new(defaults_table(), "default");
+        layered.merge_file(&system, false, &mut Vec::new()).unwrap();
+        layered.merge_file(&user, false, &mut Vec::new()).unwrap();
+        let explained = layered.explain();
+
+        assert_eq!(layered.get("theme"), Some(&string("ocean")));
+        assert_eq!(layered.get("font_size"), Some(&toml::Value::Integer(18)));
+        assert_eq!(layered.get("prompts.apps"), Some(&string("run")));
+        assert_eq!(layered.get("prompts.dmenu"), Some(&string("pick")));
+        assert_eq!(layered.get("prompts.settings"), Some(&string("Settings")));
+
+        let line = |key: &str| explained.lines().find(|l| l.starts_with(&format!("{} =", key))).unwrap().to_string();
+        assert!(line("theme").ends_with(&format!("# {}", system.display())), "{}", explained);
+        assert!(line("font_size").ends_with(&format!("# {}", user.display())), "{}", explained);
+        assert!(line("prompts.settings").ends_with("# default"), "{}", explained);
+    }
+
+    #[test]
+    fn includes_override_the_file_that_includes_them() {
+        let dir = ScratchDir::new("config-include");
+        std::fs::create_dir_all(dir.join("conf.d")).unwrap();
+        let user = dir.join("config.toml");
+        std::fs::write(&user, "include = [\"conf.d/a.toml\", \"missing.toml\"]\ntheme = \"ocean\"\nfont_size = 12\n").unwrap();
+        std::fs::write(dir.join("conf.d/a.toml"), "font_size = 20\n").unwrap();
+        let mut layered = Layered::new(defaults_table(), "default");
+        layered.merge_file(&user, false, &mut Vec::new()).unwrap();
+
+        assert_eq!(layered.get("theme"), Some(&string("ocean")));
+        assert_eq!(layered.get("font_size"), Some(&toml::Value::Integer(20)));
+        assert!(layered.get("include").is_none());
+        assert_eq!(layered.warnings.len(), 1);
+        assert!(layered.warnings[0].contains("missing.toml"), "{}", layered.warnings[0]);
+    }
+
+    #[test]
+    fn include_cycles_are_errors() {
+        let dir = ScratchDir::new("config-cycle");
+        std::fs::write(dir.join("a.toml"), "include = [\"b.toml\"]\nfont_size = 12\n").unwrap();
+        std::fs::write(dir.join("b.toml"), "include = [\"a.toml\"]\nfont_size = 14\n").unwrap();
+        let result = Layered::new(defaults_table(), "default").merge_file(&dir.join("a.toml"), false, &mut Vec::new());
+        let mut layered = lenient();
+        layered.merge_file(&dir.join("a.toml"), false, &mut Vec::new()).unwrap();
+
+        assert!(matches!(result, Err(ConfigError::IncludeCycle(path)) if path.ends_with("a.toml")));
+        // Leniently, both files still apply and the cycle is reported once
+        assert_eq!(layered.get("font_size"), Some(&toml::Value::Integer(14)));
+        assert_eq!(layered.errors.len(), 1);
+    }
+
+    #[test]
+    fn environment_variables_take_the_key_type() {
+        let _env = ENV.lock().unwrap();
+        let vars = [
+            ("FMENU_FONT_SIZE", "16"),
+            ("FMENU_FULLSCREEN", "yes"),
+            ("FMENU_WIDTH", "50%"),
+            ("FMENU_SEARCH_PATHS", "/a:/b:"),
+            ("FMENU_SOURCE_LIMITS__APPS", "5"),
+            ("FMENU_TERMINAL", "kitty"),
+            ("FMENU_NO_SUCH_KEY", "1"),
+        ];
+        for (var, value) in vars {
+            std::env::set_var(var, value);
+        }
+        let mut layered = Layered::new(defaults_table(), "default");
+        let result = layered.merge_env();
+        for (var, _) in vars {
+            std::env::remove_var(var);
+        }
+        result.unwrap();
+
+        assert_eq!(layered.get("font_size"), Some(&toml::Value::Integer(16)));
+        assert_eq!(layered.get("fullscreen"), Some(&toml::Value::Boolean(true)));
+        assert_eq!(layered.get("width"), Some(&string("50%")));
+        assert_eq!(layered.get("search_paths"), Some(&toml::Value::Array(vec![string("/a"), string("/b")])));
+        assert_eq!(layered.get("source_limits.apps"), Some(&toml::Value::Integer(5)));
+        assert_eq!(layered.get("terminal"), Some(&string("kitty")));
+        assert_eq!(layered.warnings, ["FMENU_NO_SUCH_KEY: no config key `no_such_key`"]);
+        assert!(layered.explain().contains("# $FMENU_SOURCE_LIMITS__APPS"));
+    }
+
+    #[test]
+    fn dimensions() {
+        assert_eq!("640".parse(), Ok(Dimension::Pixels(640)));
+        assert_eq!(" 50% ".parse(), Ok(Dimension::Percent(50)));
+        assert_eq!("100 %".parse(), Ok(Dimension::Percent(100)));
+        for input in ["0%", "101%", "-5", "wide", "50.5%", ""] {
+            assert!(input.parse::<Dimension>().is_err(), "`{}` should not parse", input);
+        }
+        assert_eq!(Dimension::Percent(50).resolve(1920), 960);
+        assert_eq!(Dimension::Pixels(640).resolve(1920), 640);
+        assert_eq!(Dimension::Percent(25).to_string(), "25%");
+    }
 }
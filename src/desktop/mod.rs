@@ -0,0 +1,101 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+// The parts of a `.desktop` file the launcher shows
+#[derive(Debug, Clone, Default)]
+pub struct DesktopEntry {
+    pub name: String,
+    pub generic_name: Option<String>,
+    pub comment: Option<String>,
+    pub exec: Option<String>,
+    pub icon: Option<String>,
+    pub categories: Vec<String>,
+    pub terminal: bool,
+}
+
+impl DesktopEntry {
+    pub fn parse(content: &str) -> Option<Self> {
+        let mut entry = Self::default();
+        let mut in_main_group = false;
+        let mut hidden = false;
+        for line in content.lines().map(str::trim) {
+            if line.starts_with('[') {
+                in_main_group = line == "[Desktop Entry]";
+                continue;
+            }
+            // Localized keys such as `Name[de]` are skipped by not matching exactly
+            let Some((key, value)) = line.split_once('=').filter(|_| in_main_group) else {
+                continue;
+            };
+            let value = value.trim().to_string();
+            match key.trim() {
+                "Name" => entry.name = value,
+                "GenericName" => entry.generic_name = Some(value),
+                "Comment" => entry.comment = Some(value),
+                "Exec" => entry.exec = Some(value),
+                "Icon" => entry.icon = Some(value),
+                "Categories" => entry.categories = value.split(';').filter(|c| !c.is_empty()).map(str::to_string).collect(),
+                "Terminal" => entry.terminal = value == "true",
+                "NoDisplay" | "Hidden" => hidden |= value == "true",
+                _ => {}
+            }
+        }
+        (!hidden && !entry.name.is_empty()).then_some(entry)
+    }
+
+    // The program `Exec` runs, without its path, arguments or field codes
+    pub fn program(&self) -> Option<&str> {
+        let exec = self.exec.as_deref()?;
+        let mut words = exec.split_whitespace().filter(|w| *w != "env" && !w.contains('='));
+        let program = words.next()?.trim_matches('"');
+        Some(program.rsplit('/').next().unwrap_or(program))
+    }
+}
+
+// Where desktop files live, most important first
+pub fn application_dirs() -> Vec<PathBuf> {
+    data_dirs().into_iter().map(|dir| dir.join("applications")).collect()
+}
+
+// $XDG_DATA_HOME, then $XDG_DATA_DIRS
+pub fn data_dirs() -> Vec<PathBuf> {
+    let mut dirs: Vec<PathBuf> = dirs::data_dir().into_iter().collect();
+    let system = std::env::var("XDG_DATA_DIRS").unwrap_or_default();
+    let system = if system.is_empty() { "/usr/local/share:/usr/share".to_string() } else { system };
+    dirs.extend(std::env::split_paths(&system).filter(|dir| dir.is_absolute()));
+    dirs
+}
+
+// The desktop entry of an executable from `search_paths`. Desktop files are read once, on
+// the first lookup
+pub fn lookup(name: &str) -> Option<&'static DesktopEntry> {
+    static INDEX: OnceLock<HashMap<String, DesktopEntry>> = OnceLock::new();
+    INDEX.get_or_init(index).get(name)
+}
+
+// Desktop entries by the program they run and by file name, so an executable finds its
+// entry either way. Earlier directories win
+fn index() -> HashMap<String, DesktopEntry> {
+    let mut index = HashMap::new();
+    for dir in application_dirs().iter().rev() {
+        let Ok(files) = std::fs::read_dir(dir) else {
+            continue;
+        };
+        for path in files.filter_map(Result::ok).map(|file| file.path()) {
+            if path.extension().and_then(|e| e.to_str()) != Some("desktop") {
+                continue;
+            }
+            let Some(entry) = std::fs::read_to_string(&path).ok().and_then(|c| DesktopEntry::parse(&c)) else {
+                continue;
+            };
+            if let Some(program) = entry.program() {
+                index.insert(program.to_string(), entry.clone());
+            }
+            if let Some(stem) = path.file_stem().and_then(|s| s.to_str()) {
+                index.insert(stem.to_string(), entry);
+            }
+        }
+    }
+    index
+}
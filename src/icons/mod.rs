@@ -0,0 +1,146 @@
+use iced::widget::{image, svg};
+use std::collections::HashMap;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+
+use crate::desktop;
+
+// Fixed-size directories of freedesktop icon themes, largest first
+const SIZES: &[u16] = &[512, 256, 192, 128, 96, 64, 48, 36, 32, 24, 22, 16];
+
+#[derive(Debug, Clone)]
+pub enum Icon {
+    Raster(image::Handle),
+    Vector(svg::Handle),
+}
+
+// Icons by entry name, looked up in the background the first time an entry is shown
+pub struct Icons {
+    lookup: Lookup,
+    cache: HashMap<String, Option<Icon>>,
+}
+
+impl Icons {
+    pub fn new(size: u16) -> Self {
+        let mut themes: Vec<String> = gtk_icon_theme().into_iter().collect();
+        // Every theme falls back to hicolor, where applications install their icons
+        themes.push("hicolor".to_string());
+        Self {
+            lookup: Lookup { size, themes },
+            cache: HashMap::new(),
+        }
+    }
+
+    pub fn size(&self) -> u16 {
+        self.lookup.size
+    }
+
+    // None while the icon is still being looked up, or when the entry has none
+    pub fn get(&self, name: &str) -> Option<&Icon> {
+        self.cache.get(name).and_then(Option::as_ref)
+    }
+
+    // Looks up the names not seen before on a blocking thread; insert the result when it finishes
+    pub fn load(&mut self, names: &[String]) -> Option<impl Future<Output = Vec<(String, Option<Icon>)>>> {
+        let names: Vec<String> = names.iter().filter(|name| !self.cache.contains_key(*name)).cloned().collect();
+        if names.is_empty() {
+            return None;
+        }
+        // Pending names count as iconless, so they are only requested once
+        self.cache.extend(names.iter().map(|name| (name.clone(), None)));
+        let lookup = self.lookup.clone();
+        Some(async move {
+            tokio::task::spawn_blocking(move || {
+                names
+                    .into_iter()
+                    .map(|name| {
+                        let icon = lookup.find(&name).and_then(|path| load(&path));
+                        (name, icon)
+                    })
+                    .collect()
+            })
+            .await
+            .unwrap_or_default()
+        })
+    }
+
+    pub fn insert(&mut self, loaded: Vec<(String, Option<Icon>)>) {
+        self.cache.extend(loaded);
+    }
+}
+
+// What a lookup needs, cloned onto the thread that touches the filesystem
+#[derive(Clone)]
+struct Lookup {
+    size: u16,
+    themes: Vec<String>,
+}
+
+impl Lookup {
+    // The icon named by the program's desktop entry, or one named like the program
+    fn find(&self, name: &str) -> Option<PathBuf> {
+        let icon_name = desktop::lookup(name).and_then(|entry| entry.icon.as_deref()).unwrap_or(name);
+        let path = Path::new(icon_name);
+        if path.is_absolute() {
+            return path.is_file().then(|| path.to_path_buf());
+        }
+        self.find_in_themes(icon_name).or_else(|| {
+            ["png", "svg"]
+                .iter()
+                .map(|ext| PathBuf::from("/usr/share/pixmaps").join(format!("{}.{}", icon_name, ext)))
+                .find(|path| path.is_file())
+        })
+    }
+
+    fn find_in_themes(&self, icon_name: &str) -> Option<PathBuf> {
+        let bases: Vec<PathBuf> = dirs::home_dir()
+            .map(|home| home.join(".icons"))
+            .into_iter()
+            .chain(desktop::data_dirs().into_iter().map(|dir| dir.join("icons")))
+            .collect();
+        for theme in &self.themes {
+            for size_dir in self.size_dirs() {
+                for base in &bases {
+                    for ext in ["png", "svg"] {
+                        let path = base.join(theme).join(&size_dir).join("apps").join(format!("{}.{}", icon_name, ext));
+                        if path.is_file() {
+                            return Some(path);
+                        }
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    // The requested size first, then larger ones to scale down, scalable, and smaller ones last
+    fn size_dirs(&self) -> Vec<String> {
+        let mut larger: Vec<u16> = SIZES.iter().copied().filter(|s| *s >= self.size).collect();
+        larger.reverse();
+        let smaller = SIZES.iter().copied().filter(|s| *s < self.size);
+        larger
+            .into_iter()
+            .map(|s| format!("{0}x{0}", s))
+            .chain(std::iter::once("scalable".to_string()))
+            .chain(smaller.map(|s| format!("{0}x{0}", s)))
+            .collect()
+    }
+}
+
+pub fn load(path: &Path) -> Option<Icon> {
+    match path.extension().and_then(|e| e.to_str()) {
+        Some("svg") => Some(Icon::Vector(svg::Handle::from_path(path))),
+        Some("png" | "jpg" | "jpeg" | "gif" | "bmp" | "webp" | "ico") => Some(Icon::Raster(image::Handle::from_path(path))),
+        _ => None,
+    }
+}
+
+// The icon theme GTK applications use, from ~/.config/gtk-3.0/settings.ini
+fn gtk_icon_theme() -> Option<String> {
+    let settings = dirs::config_dir()?.join("gtk-3.0").join("settings.ini");
+    let content = std::fs::read_to_string(settings).ok()?;
+    content.lines().find_map(|line| {
+        let (key, value) = line.split_once('=')?;
+        (key.trim() == "gtk-icon-theme-name").then(|| value.trim().trim_matches('"').to_string())
+    })
+}
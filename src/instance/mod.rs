@@ -0,0 +1,122 @@
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Seek, Write};
+use std::os::unix::fs::OpenOptionsExt;
+use std::os::unix::io::AsRawFd;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use anyhow::{Context, Result};
+
+use crate::ipc::{self, Request};
+
+// Held for the lifetime of the running instance; the kernel drops the lock when we exit
+pub struct InstanceLock {
+    _file: File,
+}
+
+pub enum Acquired {
+    Lock(InstanceLock),
+    // Another instance is running and has been asked to toggle its window
+    Forwarded(i32),
+}
+
+fn pid_path() -> Result<PathBuf> {
+    ipc::runtime_file("pid")
+}
+
+pub fn acquire(replace: bool) -> Result<Acquired> {
+    let path = pid_path()?;
+    let mut file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .custom_flags(libc::O_NOFOLLOW)
+        .open(&path)
+        .with_context(|| format!("Failed to open {}", path.display()))?;
+
+    if !try_lock(&file) {
+        let pid = read_pid(&mut file);
+
+        if !replace {
+            // A freshly started instance may hold the lock before its socket is up
+            return match send_with_retry(&Request::Toggle, Duration::from_secs(1)) {
+                Ok(()) => Ok(Acquired::Forwarded(pid)),
+                Err(_) => anyhow::bail!(
+                    "another instance is running (pid {}) but not responding; use --replace to restart it",
+                    pid
+                ),
+            };
+        }
+
+        replace_instance(&file, pid)?;
+    }
+
+    file.set_len(0)?;
+    file.rewind()?;
+    write!(file, "{}", std::process::id())?;
+    file.flush()?;
+    Ok(Acquired::Lock(InstanceLock { _file: file }))
+}
+
+fn replace_instance(file: &File, pid: i32) -> Result<()> {
+    // Ask nicely first, then escalate for instances that stopped answering
+    let _ = ipc::send(&Request::Quit);
+    if wait_for_lock(file, Duration::from_millis(500)) {
+        return Ok(());
+    }
+
+    // The pid file may be stale and the pid reused by something else
+    let ours = is_same_program(pid);
+    for signal in [libc::SIGTERM, libc::SIGKILL] {
+        if ours {
+            log::info!("Sending signal {} to stale instance {}", signal, pid);
+            unsafe { libc::kill(pid, signal) };
+        }
+        if wait_for_lock(file, Duration::from_secs(1)) {
+            return Ok(());
+        }
+    }
+
+    anyhow::bail!("Failed to replace running instance (pid {})", pid)
+}
+
+fn send_with_retry(request: &Request, timeout: Duration) -> Result<()> {
+    let start = Instant::now();
+    loop {
+        match ipc::send(request) {
+            Ok(_) => return Ok(()),
+            Err(e) if start.elapsed() >= timeout => return Err(e),
+            Err(_) => std::thread::sleep(Duration::from_millis(50)),
+        }
+    }
+}
+
+fn is_same_program(pid: i32) -> bool {
+    if pid <= 0 {
+        return false;
+    }
+    let exe = std::fs::read_link(format!("/proc/{}/exe", pid));
+    matches!((exe, std::env::current_exe()), (Ok(theirs), Ok(ours)) if theirs == ours)
+}
+
+fn wait_for_lock(file: &File, timeout: Duration) -> bool {
+    let start = Instant::now();
+    while start.elapsed() < timeout {
+        if try_lock(file) {
+            return true;
+        }
+        std::thread::sleep(Duration::from_millis(50));
+    }
+    false
+}
+
+fn try_lock(file: &File) -> bool {
+    unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX | libc::LOCK_NB) == 0 }
+}
+
+fn read_pid(file: &mut File) -> i32 {
+    let mut content = String::new();
+    let _ = file.rewind();
+    let _ = file.read_to_string(&mut content);
+    content.trim().parse().unwrap_or(0)
+}
@@ -0,0 +1,243 @@
+use serde::{Deserialize, Serialize};
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::fs::{DirBuilderExt, MetadataExt, PermissionsExt};
+use std::os::unix::net::UnixStream;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use anyhow::{Context, Result};
+use clap::Subcommand;
+use iced::{subscription, Subscription};
+use iced::futures::{SinkExt, channel::{mpsc, oneshot}};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt};
+use tokio::net::UnixListener;
+
+use crate::ui::Mode;
+
+// Requests are sent as a single line of JSON, e.g. {"cmd":"set-query","query":"fire"}
+#[derive(Debug, Clone, Serialize, Deserialize, Subcommand)]
+#[serde(tag = "cmd", rename_all = "kebab-case")]
+pub enum Request {
+    /// Show the window
+    Show,
+    /// Hide the window
+    Hide,
+    /// Show the window if hidden, hide it otherwise
+    Toggle,
+    /// Switch to another mode
+    SetMode {
+        #[arg(value_enum)]
+        mode: Mode,
+    },
+    /// Replace the search query
+    SetQuery {
+        #[arg(default_value = "")]
+        query: String,
+    },
+    /// Set the label left of the input, for one mode or for apps and dmenu
+    SetPrompt {
+        #[arg(default_value = "")]
+        prompt: String,
+        #[arg(long, value_enum)]
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        mode: Option<Mode>,
+    },
+    /// Set the text shown in the empty input
+    SetPlaceholder {
+        placeholder: String,
+    },
+    /// Show a message above the results; leave it out to remove the message
+    SetMessage {
+        #[arg(default_value = "")]
+        message: String,
+    },
+    /// Show a list to pick from (read from stdin when no entries are given) and print the selection
+    PushEntries {
+        entries: Vec<String>,
+    },
+    /// Reload config.toml from disk
+    ReloadConfig,
+    /// Reload the current theme from disk
+    ReloadTheme,
+    /// Quit the running instance
+    Quit,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Response {
+    pub ok: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    // The entry picked from pushed entries; none when the list was dismissed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub selection: Option<String>,
+}
+
+impl Response {
+    fn ok() -> Self {
+        Self { ok: true, error: None, selection: None }
+    }
+
+    fn error(message: impl Into<String>) -> Self {
+        Self { ok: false, error: Some(message.into()), selection: None }
+    }
+
+    fn selected(selection: Option<String>) -> Self {
+        Self { selection, ..Self::ok() }
+    }
+}
+
+// Answers `push-entries` with the selection, or `quit` with anything. Dropping every copy
+// without sending answers that nothing was selected
+#[derive(Debug, Clone)]
+pub struct Reply(Arc<Mutex<Option<oneshot::Sender<Selection>>>>);
+
+// The selection, and where to signal once it has been written
+type Selection = (String, oneshot::Sender<()>);
+
+impl Reply {
+    // Finishes once the selection has been written back to the client
+    pub async fn send(self, selection: String) {
+        let sender = self.0.lock().unwrap().take();
+        if let Some(sender) = sender {
+            let (written, done) = oneshot::channel();
+            if sender.send((selection, written)).is_ok() {
+                let _ = done.await;
+            }
+        }
+    }
+}
+
+// Files shared between instances live in $XDG_RUNTIME_DIR, or else in a private
+// /tmp/5menu-$UID, so other users can neither plant them nor open them
+pub fn runtime_file(extension: &str) -> Result<PathBuf> {
+    let dir = match dirs::runtime_dir() {
+        Some(dir) => dir,
+        None => private_temp_dir()?,
+    };
+    Ok(dir.join(format!("5menu.{}", extension)))
+}
+
+fn private_temp_dir() -> Result<PathBuf> {
+    let uid = unsafe { libc::getuid() };
+    let dir = std::env::temp_dir().join(format!("5menu-{}", uid));
+    match std::fs::DirBuilder::new().mode(0o700).create(&dir) {
+        Err(e) if e.kind() != std::io::ErrorKind::AlreadyExists => {
+            return Err(e).with_context(|| format!("Failed to create {}", dir.display()));
+        }
+        _ => {}
+    }
+
+    // Not following links, so a link planted in its place is refused
+    let metadata = std::fs::symlink_metadata(&dir)?;
+    if !metadata.is_dir() || metadata.uid() != uid || metadata.mode() & 0o077 != 0 {
+        anyhow::bail!("{} must be a directory only you can access", dir.display());
+    }
+    Ok(dir)
+}
+
+pub fn socket_path() -> Result<PathBuf> {
+    runtime_file("sock")
+}
+
+pub fn send(request: &Request) -> Result<Response> {
+    let path = socket_path()?;
+    let mut stream = UnixStream::connect(&path)
+        .with_context(|| format!("No running instance listening on {}", path.display()))?;
+
+    let mut line = serde_json::to_string(request)?;
+    line.push('\n');
+    stream.write_all(line.as_bytes())?;
+
+    let mut reply = String::new();
+    BufReader::new(stream).read_line(&mut reply)?;
+    if reply.is_empty() {
+        anyhow::bail!("The running instance quit before answering");
+    }
+    Ok(serde_json::from_str(&reply)?)
+}
+
+pub fn listen() -> Subscription<(Request, Option<Reply>)> {
+    struct Listener;
+
+    subscription::channel(std::any::TypeId::of::<Listener>(), 32, |output| async move {
+        let path = match socket_path() {
+            Ok(path) => path,
+            Err(e) => {
+                log::error!("No IPC socket: {:#}", e);
+                iced::futures::future::pending().await
+            }
+        };
+        let _ = std::fs::remove_file(&path);
+
+        let listener = UnixListener::bind(&path).and_then(|listener| {
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600))?;
+            Ok(listener)
+        });
+        match listener {
+            // Each client gets its own task, so one waiting for a selection blocks no others
+            Ok(listener) => loop {
+                if let Ok((stream, _)) = listener.accept().await {
+                    let output = output.clone();
+                    tokio::spawn(async move {
+                        if let Err(e) = serve(stream, output).await {
+                            log::warn!("IPC connection failed: {}", e);
+                        }
+                    });
+                }
+            },
+            Err(e) => {
+                log::error!("Failed to bind IPC socket {}: {}", path.display(), e);
+                iced::futures::future::pending().await
+            }
+        }
+    })
+}
+
+async fn serve(stream: tokio::net::UnixStream, mut output: mpsc::Sender<(Request, Option<Reply>)>) -> Result<()> {
+    let (reader, mut writer) = stream.into_split();
+    let mut lines = tokio::io::BufReader::new(reader).lines();
+
+    while let Some(line) = lines.next_line().await? {
+        let mut written = None;
+        let response = match serde_json::from_str::<Request>(&line) {
+            Ok(request) => {
+                // Pushed entries are answered once the user picks one or dismisses the list,
+                // and quit before the instance exits, which waits for the answer to be written
+                let quit = matches!(request, Request::Quit);
+                let (reply, selection) = match request {
+                    Request::PushEntries { .. } | Request::Quit => {
+                        let (sender, receiver) = oneshot::channel();
+                        (Some(Reply(Arc::new(Mutex::new(Some(sender))))), Some(receiver))
+                    }
+                    _ => (None, None),
+                };
+                match (output.send((request, reply)).await, selection) {
+                    (Err(e), _) => Response::error(e.to_string()),
+                    (Ok(()), None) => Response::ok(),
+                    (Ok(()), Some(selection)) if quit => {
+                        if let Ok((_, done)) = selection.await {
+                            written = Some(done);
+                        }
+                        Response::ok()
+                    }
+                    (Ok(()), Some(selection)) => match selection.await {
+                        Ok((selection, done)) => {
+                            written = Some(done);
+                            Response::selected(Some(selection))
+                        }
+                        Err(_) => Response::selected(None),
+                    },
+                }
+            }
+            Err(e) => Response::error(format!("Invalid request: {}", e)),
+        };
+
+        let mut reply = serde_json::to_string(&response)?;
+        reply.push('\n');
+        writer.write_all(reply.as_bytes()).await?;
+        if let Some(done) = written {
+            let _ = done.send(());
+        }
+    }
+    Ok(())
+}
@@ -1,22 +1,98 @@
+mod cli;
 mod config;
+mod desktop;
+mod icons;
+mod instance;
+mod ipc;
+mod placement;
+mod preview;
+mod rofi;
 mod themes;
 mod ui;
+mod watcher;
 
+use clap::Parser;
 use iced::{Settings, window, Application, Font};
 use std::default::Default;
-use ui::MenuWindow;
+use cli::{Cli, Commands};
+use themes::Palette;
+use ui::{Flags, MenuWindow};
 
 fn main() -> iced::Result {
     env_logger::init();
 
-    let config = config::Config::load().unwrap_or_default();
-    
+    let cli = Cli::parse();
+
+    if let Some(command) = cli.command {
+        let result = match command {
+            Commands::Msg { request } => cli::run_msg(request),
+            Commands::Config { explain, action } => cli::run_config(explain, action),
+            Commands::Theme { action } => cli::run_theme(action),
+            Commands::ImportRofi { file, name, dry_run, force } => cli::run_import_rofi(file, &name, dry_run, force),
+        };
+        if let Err(e) = result {
+            eprintln!("5menu: {}", e);
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
+    let mut texts = ui::Texts { placeholder: cli.placeholder, message: cli.message, ..Default::default() };
+    if let Some(prompt) = cli.prompt {
+        texts.set_prompt(None, prompt);
+    }
+
+    let _lock = match instance::acquire(cli.replace) {
+        Ok(instance::Acquired::Lock(lock)) => lock,
+        Ok(instance::Acquired::Forwarded(pid)) => {
+            // The running instance takes over whatever texts were given here
+            for request in texts.requests() {
+                if let Err(e) = ipc::send(&request) {
+                    eprintln!("5menu: {}", e);
+                }
+            }
+            eprintln!("5menu is already running (pid {}); toggled its window", pid);
+            return Ok(());
+        }
+        Err(e) => {
+            eprintln!("5menu: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    if let Err(e) = config::Config::ensure_user_file() {
+        log::error!("{}", e);
+    }
+
+    // A bad key, file or FMENU_* variable is left out on its own and named in the status line
+    let (config, config_errors, warnings) = match config::Config::load_lenient() {
+        Ok(layered) => {
+            layered.print_warnings();
+            (layered.config, layered.errors, layered.warnings)
+        }
+        Err(e) => {
+            log::error!("{}; using default configuration", e);
+            (config::Config::default(), vec![e.to_string()], Vec::new())
+        }
+    };
+
+    // The theme is loaded up front because it decides the height of some layouts; an
+    // auto-sized list opens at its tallest and shrinks once the results are known
+    let (theme, status) = ui::load_theme(&config, cli.theme.as_deref());
+    let status = status.or_else(|| ui::config_status(&config_errors, &warnings));
+    let extras = ui::Extras {
+        status: status.is_some(),
+        pane: config.preview == config::PreviewPosition::Bottom,
+        message_lines: texts.message.as_deref().unwrap_or(&config.message).lines().count(),
+    };
+    let content_height = ui::content_height(&config, &Palette::from(&theme), usize::MAX, extras);
+    let placement = placement::Placement::resolve(&config, content_height);
     let window_settings = window::Settings {
-        size: (config.width, config.height),
-        position: window::Position::Centered,
+        size: (placement.width, placement.height),
+        position: placement.position(),
         min_size: None,
         max_size: None,
-        visible: true,
+        visible: !cli.daemon,
         resizable: false,
         decorations: false,
         transparent: true,
@@ -27,9 +103,18 @@ fn main() -> iced::Result {
 
     let settings = Settings {
         window: window_settings,
-        flags: (),
         default_font: Font::MONOSPACE,
         default_text_size: config.font_size as f32,
+        flags: Flags {
+            daemon: cli.daemon,
+            config,
+            theme_override: cli.theme,
+            theme,
+            status,
+            config_errors,
+            placement,
+            texts,
+        },
         antialiasing: true,
         ..Default::default()
     };
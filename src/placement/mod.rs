@@ -0,0 +1,347 @@
+use iced::window;
+use std::collections::HashMap;
+use std::process::Command;
+
+use crate::config::{Config, Layout, Location, MonitorSelector};
+
+// Used for percentages when no monitor can be detected, e.g. without xrandr
+const FALLBACK_SIZE: (u32, u32) = (1920, 1080);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Monitor {
+    pub name: String,
+    pub primary: bool,
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Monitor {
+    fn contains(&self, (x, y): (i32, i32)) -> bool {
+        x >= self.x && y >= self.y && x < self.x + self.width as i32 && y < self.y + self.height as i32
+    }
+}
+
+// Size and position of the window, worked out from the config and the monitors
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Placement {
+    pub width: u32,
+    pub height: u32,
+    // None leaves centering to the platform, when the monitors are unknown
+    pub position: Option<(i32, i32)>,
+    pub fullscreen: bool,
+}
+
+impl Placement {
+    // `content_height`, when the view knows it, replaces the configured height
+    pub fn resolve(config: &Config, content_height: Option<u32>) -> Self {
+        let monitors = monitors();
+        let Some(monitor) = select(&monitors, &config.monitor) else {
+            let (width, height) = FALLBACK_SIZE;
+            if config.location != Location::Center || config.fullscreen {
+                log::warn!("No monitors found (is xrandr installed?); centering the window");
+            }
+            let width = match config.layout {
+                Layout::Bar => width,
+                Layout::List | Layout::Grid => config.width.resolve(width),
+            };
+            return Self {
+                width,
+                height: content_height.unwrap_or_else(|| config.height.resolve(height)),
+                position: None,
+                fullscreen: config.fullscreen,
+            };
+        };
+
+        if config.fullscreen {
+            return Self {
+                width: monitor.width,
+                height: monitor.height,
+                position: Some((monitor.x, monitor.y)),
+                fullscreen: true,
+            };
+        }
+
+        let width = match config.layout {
+            Layout::Bar => monitor.width,
+            Layout::List | Layout::Grid => config.width.resolve(monitor.width).min(monitor.width),
+        };
+        let height = content_height
+            .unwrap_or_else(|| config.height.resolve(monitor.height))
+            .min(monitor.height);
+        let location = match (config.layout, config.location) {
+            // A bar spans the monitor, so only the edge it sits on is left to choose
+            (Layout::Bar, Location::Bottom | Location::BottomLeft | Location::BottomRight) => Location::Bottom,
+            (Layout::Bar, _) => Location::Top,
+            (_, location) => location,
+        };
+        let (x, y) = match location {
+            Location::AtCursor => at_cursor(&monitors, monitor, width, height),
+            location => {
+                let (horizontal, vertical) = anchors(location);
+                (
+                    align(monitor.x, monitor.width, width, horizontal),
+                    align(monitor.y, monitor.height, height, vertical),
+                )
+            }
+        };
+        Self {
+            width,
+            height,
+            position: Some((x + config.x_offset, y + config.y_offset)),
+            fullscreen: false,
+        }
+    }
+
+    // The same window at another height, with the edge its location anchors it by kept in place
+    pub fn with_height(&self, config: &Config, height: u32) -> Self {
+        let position = self.position.map(|(x, y)| {
+            let vertical = match config.location {
+                Location::AtCursor => Anchor::Start,
+                location => anchors(location).1,
+            };
+            let y = match vertical {
+                Anchor::Start => y,
+                // Halving each height separately keeps the middle from drifting over many resizes
+                Anchor::Middle => y + (self.height / 2) as i32 - (height / 2) as i32,
+                Anchor::End => y + self.height as i32 - height as i32,
+            };
+            (x, y)
+        });
+        Self { height, position, ..*self }
+    }
+
+    pub fn position(&self) -> window::Position {
+        match self.position {
+            Some((x, y)) => window::Position::Specific(x, y),
+            None => window::Position::Centered,
+        }
+    }
+
+    pub fn mode(&self) -> window::Mode {
+        if self.fullscreen {
+            window::Mode::Fullscreen
+        } else {
+            window::Mode::Windowed
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Anchor {
+    Start,
+    Middle,
+    End,
+}
+
+fn anchors(location: Location) -> (Anchor, Anchor) {
+    match location {
+        Location::Center | Location::AtCursor => (Anchor::Middle, Anchor::Middle),
+        Location::Top => (Anchor::Middle, Anchor::Start),
+        Location::Bottom => (Anchor::Middle, Anchor::End),
+        Location::Left => (Anchor::Start, Anchor::Middle),
+        Location::Right => (Anchor::End, Anchor::Middle),
+        Location::TopLeft => (Anchor::Start, Anchor::Start),
+        Location::TopRight => (Anchor::End, Anchor::Start),
+        Location::BottomLeft => (Anchor::Start, Anchor::End),
+        Location::BottomRight => (Anchor::End, Anchor::End),
+    }
+}
+
+fn align(start: i32, space: u32, size: u32, anchor: Anchor) -> i32 {
+    let free = space.saturating_sub(size) as i32;
+    match anchor {
+        Anchor::Start => start,
+        Anchor::Middle => start + free / 2,
+        Anchor::End => start + free,
+    }
+}
+
+// The window's corner goes to the pointer, kept inside the pointer's monitor
+fn at_cursor(monitors: &[Monitor], fallback: &Monitor, width: u32, height: u32) -> (i32, i32) {
+    let Some(cursor) = cursor() else {
+        return (
+            align(fallback.x, fallback.width, width, Anchor::Middle),
+            align(fallback.y, fallback.height, height, Anchor::Middle),
+        );
+    };
+    let monitor = monitors.iter().find(|m| m.contains(cursor)).unwrap_or(fallback);
+    let x = cursor.0.min(monitor.x + monitor.width.saturating_sub(width) as i32);
+    let y = cursor.1.min(monitor.y + monitor.height.saturating_sub(height) as i32);
+    (x, y)
+}
+
+fn select<'a>(monitors: &'a [Monitor], selector: &MonitorSelector) -> Option<&'a Monitor> {
+    let primary = || monitors.iter().find(|m| m.primary).or(monitors.first());
+    let name = match selector {
+        MonitorSelector::Index(index) => return monitors.get(*index).or_else(primary),
+        MonitorSelector::Name(name) => name.as_str(),
+    };
+    match name {
+        "primary" => primary(),
+        // The monitor of the focused window, or failing that the one with the pointer
+        "focused" => active_window_center()
+            .or_else(cursor)
+            .and_then(|point| monitors.iter().find(|m| m.contains(point)))
+            .or_else(primary),
+        _ => match name.parse::<usize>() {
+            Ok(index) => monitors.get(index),
+            Err(_) => monitors.iter().find(|m| m.name == name),
+        }
+        .or_else(|| {
+            log::warn!("Monitor {} not found; using the primary monitor", name);
+            primary()
+        }),
+    }
+}
+
+pub fn monitors() -> Vec<Monitor> {
+    run("xrandr", &["--listactivemonitors"]).map_or_else(Vec::new, |output| parse_monitors(&output))
+}
+
+// ` 0: +*eDP-1 1920/344x1080/194+0+0  eDP-1`, with `*` marking the primary monitor
+fn parse_monitors(output: &str) -> Vec<Monitor> {
+    output
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let mut parts = line.split_whitespace().skip(1);
+            let flags = parts.next()?;
+            let geometry = parts.next()?;
+            let name = parts.next().unwrap_or_else(|| flags.trim_start_matches(['+', '*']));
+
+            let (size, offset) = geometry.split_once('+')?;
+            let (x, y) = offset.split_once('+')?;
+            let (width, height) = size.split_once('x')?;
+            let pixels = |s: &str| s.split('/').next()?.parse::<u32>().ok();
+            Some(Monitor {
+                name: name.to_string(),
+                primary: flags.contains('*'),
+                x: x.parse().ok()?,
+                y: y.parse().ok()?,
+                width: pixels(width)?,
+                height: pixels(height)?,
+            })
+        })
+        .collect()
+}
+
+fn cursor() -> Option<(i32, i32)> {
+    let output = run("xdotool", &["getmouselocation", "--shell"])?;
+    let vars = shell_vars(&output);
+    Some((vars.get("X")?.parse().ok()?, vars.get("Y")?.parse().ok()?))
+}
+
+fn active_window_center() -> Option<(i32, i32)> {
+    let output = run("xdotool", &["getactivewindow", "getwindowgeometry", "--shell"])?;
+    let vars = shell_vars(&output);
+    let value = |key: &str| vars.get(key)?.parse::<i32>().ok();
+    Some((value("X")? + value("WIDTH")? / 2, value("Y")? + value("HEIGHT")? / 2))
+}
+
+// `KEY=value` lines as printed by `xdotool ... --shell`
+fn shell_vars(output: &str) -> HashMap<&str, &str> {
+    output.lines().filter_map(|line| line.split_once('=')).collect()
+}
+
+fn run(program: &str, args: &[&str]) -> Option<String> {
+    let output = Command::new(program).args(args).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    String::from_utf8(output.stdout).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const XRANDR: &str = "\
+Monitors: 2
+ 0: +*eDP-1 1920/344x1080/194+0+0  eDP-1
+ 1: +HDMI-1 2560/597x1440/336+1920+0  HDMI-1
+";
+
+    fn monitor(name: &str) -> Monitor {
+        parse_monitors(XRANDR).into_iter().find(|m| m.name == name).unwrap()
+    }
+
+    fn selected(selector: MonitorSelector) -> String {
+        select(&parse_monitors(XRANDR), &selector).unwrap().name.clone()
+    }
+
+    fn name(name: &str) -> MonitorSelector {
+        MonitorSelector::Name(name.to_string())
+    }
+
+    #[test]
+    fn parses_xrandr_monitors() {
+        assert_eq!(
+            parse_monitors(XRANDR),
+            [
+                Monitor { name: "eDP-1".to_string(), primary: true, x: 0, y: 0, width: 1920, height: 1080 },
+                Monitor { name: "HDMI-1".to_string(), primary: false, x: 1920, y: 0, width: 2560, height: 1440 },
+            ]
+        );
+    }
+
+    #[test]
+    fn skips_lines_it_cannot_read() {
+        let output = "Monitors: 2\n 0: +*eDP-1 garbage  eDP-1\n 1: +DP-2 800/1x600/1+10+20\n";
+        assert_eq!(
+            parse_monitors(output),
+            [Monitor { name: "DP-2".to_string(), primary: false, x: 10, y: 20, width: 800, height: 600 }]
+        );
+        assert!(parse_monitors("").is_empty());
+    }
+
+    #[test]
+    fn selects_monitors() {
+        assert_eq!(selected(name("primary")), "eDP-1");
+        assert_eq!(selected(name("HDMI-1")), "HDMI-1");
+        assert_eq!(selected(name("1")), "HDMI-1");
+        assert_eq!(selected(MonitorSelector::Index(1)), "HDMI-1");
+        // Unknown monitors fall back to the primary one
+        assert_eq!(selected(name("DP-9")), "eDP-1");
+        assert_eq!(selected(MonitorSelector::Index(7)), "eDP-1");
+        assert!(select(&[], &name("primary")).is_none());
+    }
+
+    #[test]
+    fn selects_the_first_monitor_without_a_primary() {
+        let monitors: Vec<Monitor> = parse_monitors(XRANDR)
+            .into_iter()
+            .rev()
+            .map(|m| Monitor { primary: false, ..m })
+            .collect();
+        assert_eq!(select(&monitors, &name("primary")).unwrap().name, "HDMI-1");
+    }
+
+    #[test]
+    fn aligns_within_the_monitor() {
+        let hdmi = monitor("HDMI-1");
+        assert_eq!(align(hdmi.x, hdmi.width, 560, Anchor::Start), 1920);
+        assert_eq!(align(hdmi.x, hdmi.width, 560, Anchor::Middle), 2920);
+        assert_eq!(align(hdmi.x, hdmi.width, 560, Anchor::End), 3920);
+        // A window wider than the monitor starts at its edge
+        assert_eq!(align(0, 100, 300, Anchor::End), 0);
+    }
+
+    #[test]
+    fn keeps_the_anchored_edge_when_resizing() {
+        let placement = Placement { width: 900, height: 600, position: Some((100, 200)), fullscreen: false };
+        let at = |location| {
+            let config = Config { location, ..Config::default() };
+            placement.with_height(&config, 300).position
+        };
+        assert_eq!(at(Location::Top), Some((100, 200)));
+        assert_eq!(at(Location::Center), Some((100, 350)));
+        assert_eq!(at(Location::BottomRight), Some((100, 500)));
+        assert_eq!(at(Location::AtCursor), Some((100, 200)));
+        assert_eq!(placement.with_height(&Config::default(), 300).height, 300);
+
+        let centered = Placement { position: None, ..placement };
+        assert_eq!(centered.with_height(&Config::default(), 300).position, None);
+    }
+}
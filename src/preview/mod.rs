@@ -0,0 +1,186 @@
+use std::path::{Path, PathBuf};
+use tokio::io::AsyncReadExt;
+
+use crate::desktop;
+use crate::icons::{self, Icon};
+
+// Only the start of a file or of a command's output is shown
+const MAX_BYTES: u64 = 64 * 1024;
+const MAX_DIR_ENTRIES: usize = 200;
+
+// What the pane shows for one entry
+#[derive(Debug, Clone)]
+pub struct Content {
+    pub image: Option<Icon>,
+    pub text: String,
+}
+
+impl Content {
+    fn text(text: impl Into<String>) -> Self {
+        Self { image: None, text: text.into() }
+    }
+}
+
+// What to preview, worked out from the mode and the selected entry
+#[derive(Debug, Clone)]
+pub enum Target {
+    App { name: String, search_paths: Vec<PathBuf> },
+    File(PathBuf),
+    // A shell command with the entry already substituted in
+    Command(String),
+}
+
+impl Target {
+    // A dmenu entry is previewed by `preview_command` when there is one, or as a file
+    // when it names one
+    pub fn for_entry(entry: &str, preview_command: &str) -> Option<Self> {
+        if !preview_command.trim().is_empty() {
+            return Some(Target::Command(preview_command.replace("{}", &shell_quote(entry))));
+        }
+        let path = match entry.strip_prefix("~/") {
+            Some(rest) => dirs::home_dir()?.join(rest),
+            None => PathBuf::from(entry),
+        };
+        path.exists().then_some(Target::File(path))
+    }
+}
+
+pub async fn load(target: Target) -> Content {
+    let result = match target {
+        // The first lookup reads every desktop file
+        Target::App { name, search_paths } => tokio::task::spawn_blocking(move || app(&name, &search_paths))
+            .await
+            .map_err(anyhow::Error::from),
+        Target::File(path) => file(&path).await,
+        Target::Command(command) => run(&command).await,
+    };
+    result.unwrap_or_else(|e| Content::text(format!("{:#}", e)))
+}
+
+// The desktop entry's description and command line, or where the executable is
+fn app(name: &str, search_paths: &[PathBuf]) -> Content {
+    let mut lines = Vec::new();
+    if let Some(entry) = desktop::lookup(name) {
+        lines.push(entry.name.clone());
+        lines.extend(entry.generic_name.iter().chain(&entry.comment).cloned());
+        lines.push(String::new());
+        if let Some(exec) = &entry.exec {
+            lines.push(format!("Exec: {}", exec));
+        }
+        if !entry.categories.is_empty() {
+            lines.push(format!("Categories: {}", entry.categories.join(", ")));
+        }
+        if entry.terminal {
+            lines.push("Runs in a terminal".to_string());
+        }
+    }
+    if let Some(path) = search_paths.iter().map(|dir| dir.join(name)).find(|path| path.is_file()) {
+        lines.push(format!("Path: {}", path.display()));
+    }
+    Content::text(lines.join("\n"))
+}
+
+async fn file(path: &Path) -> anyhow::Result<Content> {
+    let metadata = tokio::fs::metadata(path).await?;
+    if metadata.is_dir() {
+        return directory(path).await;
+    }
+    let size = format!("{} ({} bytes)", path.display(), metadata.len());
+    if let Some(image) = icons::load(path) {
+        return Ok(Content { image: Some(image), text: size });
+    }
+
+    let mut bytes = Vec::new();
+    tokio::fs::File::open(path).await?.take(MAX_BYTES).read_to_end(&mut bytes).await?;
+    if bytes.contains(&0) {
+        return Ok(Content::text(format!("Binary file {}", size)));
+    }
+    Ok(Content::text(String::from_utf8_lossy(&bytes)))
+}
+
+async fn directory(path: &Path) -> anyhow::Result<Content> {
+    let mut names = Vec::new();
+    let mut entries = tokio::fs::read_dir(path).await?;
+    while let Some(entry) = entries.next_entry().await? {
+        let mut name = entry.file_name().to_string_lossy().into_owned();
+        if entry.file_type().await.is_ok_and(|t| t.is_dir()) {
+            name.push('/');
+        }
+        names.push(name);
+    }
+    names.sort();
+    names.truncate(MAX_DIR_ENTRIES);
+    Ok(Content::text(names.join("\n")))
+}
+
+// The command, and whatever it started, is killed if the selection moves before it finishes,
+// or once it has printed more than is shown
+async fn run(command: &str) -> anyhow::Result<Content> {
+    let mut child = tokio::process::Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .stdin(std::process::Stdio::null())
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .process_group(0)
+        .spawn()?;
+    let group = child.id().map(ProcessGroup);
+    let stdout = child.stdout.take().expect("stdout is piped");
+    let stderr = child.stderr.take().expect("stderr is piped");
+
+    // Read alongside stdout so a command that fills the stderr pipe does not stall
+    let errors = tokio::spawn(async move {
+        let mut bytes = Vec::new();
+        stderr.take(MAX_BYTES).read_to_end(&mut bytes).await.map(|_| bytes)
+    });
+    let mut output = Vec::new();
+    stdout.take(MAX_BYTES).read_to_end(&mut output).await?;
+
+    if output.len() as u64 == MAX_BYTES {
+        // Still printing, like `yes` or `tail -f`; the rest would never be shown
+        drop(group);
+        let _ = child.wait().await;
+        return Ok(Content::text(String::from_utf8_lossy(&output)));
+    }
+    let status = child.wait().await?;
+    std::mem::forget(group);
+
+    if output.is_empty() && !status.success() {
+        output = errors.await??;
+    }
+    Ok(Content::text(String::from_utf8_lossy(&output)))
+}
+
+// Kills a preview command's process group when its load is dropped unfinished
+struct ProcessGroup(u32);
+
+impl Drop for ProcessGroup {
+    fn drop(&mut self) {
+        unsafe { libc::kill(-(self.0 as i32), libc::SIGKILL) };
+    }
+}
+
+// `it's` -> `'it'\''s'`, so the entry reaches the command as one word
+fn shell_quote(text: &str) -> String {
+    format!("'{}'", text.replace('\'', r"'\''"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn stops_commands_that_keep_printing() {
+        let content = tokio::time::timeout(std::time::Duration::from_secs(10), run("yes"))
+            .await
+            .expect("`yes` should be cut off")
+            .unwrap();
+        assert_eq!(content.text.len() as u64, MAX_BYTES);
+    }
+
+    #[tokio::test]
+    async fn shows_errors_of_failed_commands() {
+        let content = run("echo oops >&2; exit 1").await.unwrap();
+        assert_eq!(content.text, "oops\n");
+    }
+}
@@ -0,0 +1,527 @@
+mod parser;
+
+use anyhow::Result;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use crate::config::insert;
+use crate::themes::ThemeColor;
+use parser::{unquote, Document, Property};
+
+// rofi widgets and the theme section each one maps onto; later selectors win
+const SECTIONS: &[(&str, &[&str])] = &[
+    ("window", &["window"]),
+    ("input", &["inputbar", "entry"]),
+    ("prompt", &["prompt"]),
+    ("list", &["listview"]),
+    ("row", &["element", "element.normal.normal", "element-text"]),
+    ("selected_row", &["element.selected", "element.selected.normal", "element-text.selected"]),
+    ("alternate_row", &["element.alternate.normal"]),
+    ("status_bar", &["message", "textbox"]),
+];
+
+// The color names rofi's own themes define in `*`, for themes that style through them
+const LEGACY_COLORS: &[(&str, &str)] = &[
+    ("normal-background", "background_color"),
+    ("background", "background_color"),
+    ("normal-foreground", "text_color"),
+    ("foreground", "text_color"),
+    ("selected-normal-background", "selected_background_color"),
+    ("selected-normal-foreground", "selected_text_color"),
+    ("border-color", "border_color"),
+];
+
+// `location` in old configs counts clockwise from the top left, with 0 for the center
+const NUMBERED_LOCATIONS: &[&str] = &[
+    "center", "top-left", "top", "top-right", "right", "bottom-right", "bottom", "bottom-left", "left",
+];
+
+const DEFAULT_FONT_SIZE: f32 = 14.0;
+
+pub fn default_config() -> PathBuf {
+    dirs::config_dir()
+        .unwrap_or_else(|| PathBuf::from("~/.config"))
+        .join("rofi")
+        .join("config.rasi")
+}
+
+// What a rofi setup turned into
+pub struct Import {
+    pub theme: toml::Table,
+    // Config keys with values in the syntax `5menu config set` accepts
+    pub config: Vec<(String, String)>,
+    pub skipped: Vec<String>,
+    pub approximated: Vec<String>,
+}
+
+pub fn import(path: &Path, name: &str) -> Result<Import> {
+    let mut document = Document::load(path)?;
+    // Before @theme existed the theme was a configuration option
+    if let Some(theme) = document.get("configuration", "theme").cloned() {
+        document.apply_theme(&unquote(&theme.value), path)?;
+    }
+
+    let mut converter = Converter {
+        document: &document,
+        used: HashSet::new(),
+        variables: HashSet::new(),
+        approximated: Vec::new(),
+        font_size: DEFAULT_FONT_SIZE,
+    };
+    let (theme, config) = converter.convert(name);
+
+    // Report each property once, by its winning definition
+    let mut seen = HashSet::new();
+    let mut skipped = document.skipped.clone();
+    for property in document.properties.iter().rev() {
+        let key = (property.selector.as_str(), property.name.as_str());
+        if !seen.insert(key) || converter.used.contains(&key) {
+            continue;
+        }
+        // Custom entries in `*` are variables; they count as imported once something uses them
+        if property.selector == "*" && converter.variables.contains(&property.name) {
+            continue;
+        }
+        skipped.push(format!("{}: {} {{ {} }}", property.location, property.selector, property.name));
+    }
+    skipped.reverse();
+
+    Ok(Import {
+        theme,
+        config,
+        skipped,
+        approximated: converter.approximated,
+    })
+}
+
+struct Converter<'a> {
+    document: &'a Document,
+    used: HashSet<(&'a str, &'a str)>,
+    variables: HashSet<String>,
+    approximated: Vec<String>,
+    font_size: f32,
+}
+
+impl<'a> Converter<'a> {
+    fn convert(&mut self, name: &str) -> (toml::Table, Vec<(String, String)>) {
+        let mut theme = toml::Table::new();
+        let mut config = Vec::new();
+        theme.insert("name".to_string(), name.into());
+        theme.insert("extends".to_string(), "default".into());
+
+        // The font decides what `em` means for every distance that follows
+        for selector in ["configuration", "*", "window"] {
+            if let Some(font) = self.take(selector, "font").and_then(|p| self.font(p)) {
+                if let Some(size) = font.size {
+                    self.font_size = size;
+                    config.push(("font_size".to_string(), (size.round() as u16).to_string()));
+                }
+                if let Some(family) = font.family {
+                    insert(&mut theme, &["window", "font", "family"], family.into());
+                }
+                if let Some(weight) = font.weight {
+                    insert(&mut theme, &["window", "font", "weight"], weight.into());
+                }
+            }
+        }
+
+        if let Some(terminal) = self.take("configuration", "terminal").and_then(|p| self.value(p)) {
+            config.push(("terminal".to_string(), unquote(&terminal)));
+        }
+        if let Some(case_sensitive) = self.take("configuration", "case-sensitive").and_then(|p| self.value(p)) {
+            config.push(("case_sensitive".to_string(), case_sensitive));
+        }
+        // rofi labels each mode; the launcher's mode is the closest to apps
+        for name in ["display-drun", "display-run"] {
+            if let Some(prompt) = self.take("configuration", name).and_then(|p| self.value(p)) {
+                config.push(("prompts.apps".to_string(), unquote(&prompt)));
+                break;
+            }
+        }
+        if let Some(placeholder) = self.take("entry", "placeholder").and_then(|p| self.value(p)) {
+            config.push(("placeholder".to_string(), unquote(&placeholder)));
+        }
+        for (selector, name) in [("configuration", "lines"), ("listview", "lines")] {
+            if let Some(lines) = self.take(selector, name).and_then(|p| self.value(p)) {
+                if lines.parse::<usize>().is_ok() {
+                    config.push(("max_entries".to_string(), lines));
+                }
+            }
+        }
+        // A listview without a fixed height shrinks to its rows
+        if let Some(fixed) = self.take("listview", "fixed-height").and_then(|p| self.value(p)) {
+            if fixed.trim() == "false" {
+                config.push(("auto_height".to_string(), "true".to_string()));
+            }
+        }
+        for (name, key) in [("width", "width"), ("height", "height")] {
+            let Some(property) = self.document.get("window", name) else {
+                continue;
+            };
+            let value = self.value(property).unwrap_or_default();
+            // `50%` is relative to the monitor in both; the config takes it as it is
+            let percent = value.trim().strip_suffix('%').and_then(|p| p.trim().parse::<f32>().ok());
+            if let Some(percent) = percent.filter(|p| (1.0..=100.0).contains(p)) {
+                self.used.insert(("window", name));
+                config.push((key.to_string(), format!("{}%", percent.round())));
+            } else if let Some(Length { value, exact: true }) = self.length(&value) {
+                self.used.insert(("window", name));
+                config.push((key.to_string(), (value.round() as u32).to_string()));
+            }
+        }
+        self.placement(&mut config);
+
+        for &(rofi, field) in LEGACY_COLORS {
+            if let Some(color) = self.take("*", rofi).and_then(|p| self.color(p)) {
+                theme.insert(field.to_string(), color.into());
+            }
+        }
+        if let Some(color) = self.take("*", "alternate-normal-background").and_then(|p| self.color(p)) {
+            insert(&mut theme, &["alternate_row", "background"], color.into());
+        }
+        for (rofi, field) in [("background-color", "background_color"), ("text-color", "text_color")] {
+            if let Some(color) = self.take("*", rofi).and_then(|p| self.color(p)) {
+                theme.insert(field.to_string(), color.into());
+            }
+        }
+        if let Some(width) = self.take("*", "border").and_then(|p| self.border(p)) {
+            theme.insert("border_width".to_string(), width.into());
+        }
+        if let Some(radius) = self.take("*", "border-radius").and_then(|p| self.distance(p)) {
+            theme.insert("border_radius".to_string(), radius.into());
+        }
+
+        for &(section, selectors) in SECTIONS {
+            for &selector in selectors {
+                self.section(&mut theme, section, selector);
+            }
+        }
+
+        (theme, config)
+    }
+
+    fn section(&mut self, theme: &mut toml::Table, section: &str, selector: &'a str) {
+        let colors = [("background-color", "background"), ("text-color", "text_color"), ("border-color", "border_color")];
+        for (rofi, field) in colors {
+            if let Some(color) = self.take(selector, rofi).and_then(|p| self.color(p)) {
+                insert(theme, &[section, field], color.into());
+            }
+        }
+        if let Some(width) = self.take(selector, "border").and_then(|p| self.border(p)) {
+            insert(theme, &[section, "border_width"], width.into());
+        }
+        let distances = [("border-radius", "border_radius"), ("padding", "padding"), ("spacing", "spacing")];
+        for (rofi, field) in distances {
+            if let Some(value) = self.take(selector, rofi).and_then(|p| self.distance(p)) {
+                insert(theme, &[section, field], value.into());
+            }
+        }
+        if let Some(font) = self.take(selector, "font").and_then(|p| self.font(p)) {
+            if let Some(family) = font.family {
+                insert(theme, &[section, "font", "family"], family.into());
+            }
+            if let Some(size) = font.size {
+                insert(theme, &[section, "font", "size"], f64::from(size).into());
+            }
+            if let Some(weight) = font.weight {
+                insert(theme, &[section, "font", "weight"], weight.into());
+            }
+        }
+    }
+
+    // rofi 1.7 places the window from the theme; older configs use numbered locations
+    fn placement(&mut self, config: &mut Vec<(String, String)>) {
+        let location = match self.take("window", "location").and_then(|p| self.value(p)) {
+            Some(location) => compass_location(&location),
+            None => self
+                .take("configuration", "location")
+                .and_then(|p| self.value(p))
+                .and_then(|l| NUMBERED_LOCATIONS.get(l.trim().parse::<usize>().ok()?).copied()),
+        };
+        if let Some(location) = location {
+            config.push(("location".to_string(), location.to_string()));
+        }
+
+        for (rofi, legacy, key) in [("x-offset", "xoffset", "x_offset"), ("y-offset", "yoffset", "y_offset")] {
+            let offset = match self.take("window", rofi) {
+                Some(property) => self.distance(property),
+                None => self
+                    .take("configuration", legacy)
+                    .and_then(|p| self.value(p))
+                    .and_then(|v| v.trim().parse::<f64>().ok()),
+            };
+            if let Some(offset) = offset {
+                config.push((key.to_string(), (offset.round() as i32).to_string()));
+            }
+        }
+
+        for selector in ["configuration", "window"] {
+            if let Some(fullscreen) = self.take(selector, "fullscreen").and_then(|p| self.value(p)) {
+                config.push(("fullscreen".to_string(), fullscreen.trim().to_string()));
+            }
+        }
+
+        // `-m`: negative numbers pick the focused monitor in some way, -3 follows the pointer
+        if let Some(monitor) = self.take("configuration", "m").and_then(|p| self.value(p)) {
+            match unquote(&monitor).as_str() {
+                "-3" => config.push(("location".to_string(), "at-cursor".to_string())),
+                m if m.starts_with('-') => config.push(("monitor".to_string(), "focused".to_string())),
+                m if !m.is_empty() => config.push(("monitor".to_string(), m.to_string())),
+                _ => {}
+            }
+        }
+    }
+
+    // The winning definition of a property, marked as imported
+    fn take(&mut self, selector: &'a str, name: &'a str) -> Option<&'a Property> {
+        let property = self.document.get(selector, name)?;
+        self.used.insert((selector, name));
+        Some(property)
+    }
+
+    // Follows `@name` and `var(name, fallback)` references; None for `inherit`
+    fn value(&mut self, property: &Property) -> Option<String> {
+        let mut value = property.value.clone();
+        for _ in 0..16 {
+            let reference = if let Some(name) = value.strip_prefix('@') {
+                Some((name.trim().to_string(), None))
+            } else if let Some(args) = value.strip_prefix("var(").and_then(|v| v.strip_suffix(')')) {
+                let (name, fallback) = args.split_once(',').map_or((args, None), |(n, f)| (n, Some(f.trim())));
+                Some((name.trim().to_string(), fallback.map(str::to_string)))
+            } else {
+                None
+            };
+            let Some((name, fallback)) = reference else {
+                return (value != "inherit").then_some(value);
+            };
+
+            self.variables.insert(name.clone());
+            let target = self
+                .document
+                .get(&property.selector, &name)
+                .or_else(|| self.document.get("*", &name));
+            value = match (target, fallback) {
+                (Some(target), _) => target.value.clone(),
+                (None, Some(fallback)) => fallback,
+                (None, None) => {
+                    self.approximated.push(format!("{}: unknown variable @{}", property.location, name));
+                    return None;
+                }
+            };
+        }
+        self.approximated.push(format!("{}: variables refer to each other", property.location));
+        None
+    }
+
+    fn color(&mut self, property: &Property) -> Option<String> {
+        let value = self.value(property)?;
+        // rofi allows `rgba ( 1, 2, 3, 50 % )` and `argb:80112233`
+        let mut color: String = value.split_whitespace().collect::<Vec<_>>().join(" ");
+        color = color.replace(" (", "(").replace("( ", "(").replace(" )", ")").replace(" %", "%");
+        if let Some(argb) = color.strip_prefix("argb:") {
+            if argb.len() == 8 && argb.is_ascii() {
+                color = format!("#{}{}", &argb[2..], &argb[..2]);
+            }
+        }
+        match color.parse::<ThemeColor>() {
+            Ok(_) => Some(color),
+            Err(e) => {
+                self.approximated.push(format!("{}: {} not imported: {}", property.location, property.name, e));
+                None
+            }
+        }
+    }
+
+    fn distance(&mut self, property: &Property) -> Option<f64> {
+        let value = self.value(property)?;
+        let length = self.length(&value);
+        match length {
+            Some(Length { value, exact }) => {
+                if !exact {
+                    self.approximated.push(format!(
+                        "{}: {} `{}` became {}", property.location, property.name, property.value, value
+                    ));
+                }
+                Some(f64::from(value))
+            }
+            None => {
+                self.approximated.push(format!("{}: {} `{}` not imported", property.location, property.name, value));
+                None
+            }
+        }
+    }
+
+    // Only the width of `2px solid` or `0 0 2px 0` carries over
+    fn border(&mut self, property: &Property) -> Option<f64> {
+        let value = self.value(property)?;
+        let widths: Vec<f32> = value
+            .split_whitespace()
+            .filter_map(|part| self.length(part))
+            .map(|length| length.value)
+            .collect();
+        let width = widths.iter().copied().fold(None, |max: Option<f32>, w| Some(max.map_or(w, |m| m.max(w))))?;
+        if widths.iter().any(|w| *w != width) || value.contains("dash") {
+            self.approximated.push(format!("{}: border `{}` became {}", property.location, value, width));
+        }
+        Some(f64::from(width))
+    }
+
+    // Parses `8px`, `0.5em`, `1ch` or `8px 12px`; sides that differ are averaged into one value
+    fn length(&self, value: &str) -> Option<Length> {
+        let mut values = Vec::new();
+        let mut exact = true;
+        for part in value.split_whitespace() {
+            let number_end = part.find(|c: char| !(c.is_ascii_digit() || c == '.' || c == '-')).unwrap_or(part.len());
+            let number: f32 = part[..number_end].parse().ok()?;
+            let pixels = match &part[number_end..] {
+                "" | "px" => number,
+                "em" => number * self.font_size,
+                "ch" => {
+                    exact = false;
+                    number * self.font_size * 0.6
+                }
+                "mm" => {
+                    exact = false;
+                    number * 96.0 / 25.4
+                }
+                _ => return None,
+            };
+            values.push(pixels);
+        }
+        let first = *values.first()?;
+        if values.iter().any(|v| *v != first) {
+            exact = false;
+        }
+        let value = values.iter().sum::<f32>() / values.len() as f32;
+        Some(Length { value, exact })
+    }
+
+    // Pango font descriptions: "Iosevka Term Bold 12"
+    fn font(&mut self, property: &Property) -> Option<Font> {
+        let value = unquote(&self.value(property)?);
+        let mut words: Vec<&str> = value.split_whitespace().collect();
+        let size = words.last().and_then(|w| w.parse::<f32>().ok());
+        if size.is_some() {
+            words.pop();
+        }
+        let mut weight = None;
+        while let Some(word) = words.last() {
+            let style = match word.to_lowercase().replace('-', "").as_str() {
+                "thin" => Some("thin"),
+                "ultralight" | "extralight" => Some("extra-light"),
+                "light" => Some("light"),
+                "regular" | "normal" | "book" => Some("normal"),
+                "medium" => Some("medium"),
+                "semibold" | "demibold" => Some("semibold"),
+                "bold" => Some("bold"),
+                "ultrabold" | "extrabold" => Some("extra-bold"),
+                "heavy" | "black" => Some("black"),
+                "italic" | "oblique" => None,
+                _ => break,
+            };
+            if style.is_none() {
+                self.approximated.push(format!("{}: font style `{}` ignored", property.location, word));
+            }
+            weight = weight.or(style);
+            words.pop();
+        }
+        let family = (!words.is_empty()).then(|| words.join(" "));
+        Some(Font { family, size, weight })
+    }
+}
+
+struct Length {
+    value: f32,
+    exact: bool,
+}
+
+struct Font {
+    family: Option<String>,
+    size: Option<f32>,
+    weight: Option<&'static str>,
+}
+
+fn compass_location(location: &str) -> Option<&'static str> {
+    Some(match location.trim().to_lowercase().as_str() {
+        "center" => "center",
+        "north" => "top",
+        "south" => "bottom",
+        "west" => "left",
+        "east" => "right",
+        "northwest" => "top-left",
+        "northeast" => "top-right",
+        "southwest" => "bottom-left",
+        "southeast" => "bottom-right",
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture() -> Import {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/rofi/testdata/config.rasi");
+        import(&path, "test").unwrap()
+    }
+
+    fn theme_value<'a>(import: &'a Import, path: &[&str]) -> &'a toml::Value {
+        crate::config::lookup(&import.theme, path).unwrap_or_else(|| panic!("no {}", path.join(".")))
+    }
+
+    fn config_value<'a>(import: &'a Import, key: &str) -> Option<&'a str> {
+        import.config.iter().rev().find(|(k, _)| k == key).map(|(_, v)| v.as_str())
+    }
+
+    #[test]
+    fn theme() {
+        let import = fixture();
+        assert_eq!(theme_value(&import, &["extends"]).as_str(), Some("default"));
+        // `var()` and `@name` follow the imported variables
+        assert_eq!(theme_value(&import, &["background_color"]).as_str(), Some("#1d1f21"));
+        assert_eq!(theme_value(&import, &["text_color"]).as_str(), Some("#c5c8c6"));
+        assert_eq!(theme_value(&import, &["window", "background"]).as_str(), Some("#1d1f21"));
+        assert_eq!(theme_value(&import, &["selected_row", "background"]).as_str(), Some("#ff8800"));
+        assert_eq!(theme_value(&import, &["selected_row", "text_color"]).as_str(), Some("rgba(255, 255, 255, 100%)"));
+        // argb moves the alpha to the end
+        assert_eq!(theme_value(&import, &["window", "border_color"]).as_str(), Some("#11223380"));
+        assert_eq!(theme_value(&import, &["window", "border_width"]).as_float(), Some(2.0));
+        // `1em` at the font's size of 12
+        assert_eq!(theme_value(&import, &["border_radius"]).as_float(), Some(12.0));
+        assert_eq!(theme_value(&import, &["window", "font", "family"]).as_str(), Some("Iosevka Term"));
+        assert_eq!(theme_value(&import, &["window", "font", "weight"]).as_str(), Some("bold"));
+    }
+
+    #[test]
+    fn config() {
+        let import = fixture();
+        assert_eq!(config_value(&import, "terminal"), Some("kitty"));
+        assert_eq!(config_value(&import, "case_sensitive"), Some("true"));
+        assert_eq!(config_value(&import, "font_size"), Some("12"));
+        assert_eq!(config_value(&import, "prompts.apps"), Some("Apps:"));
+        assert_eq!(config_value(&import, "placeholder"), Some("Search…"));
+        assert_eq!(config_value(&import, "max_entries"), Some("8"));
+        assert_eq!(config_value(&import, "auto_height"), Some("true"));
+        assert_eq!(config_value(&import, "width"), Some("50%"));
+        assert_eq!(config_value(&import, "location"), Some("top"));
+    }
+
+    #[test]
+    fn reports_what_did_not_carry_over() {
+        let import = fixture();
+        let ends = |list: &[String], suffix: &str| list.iter().any(|line| line.ends_with(suffix));
+        assert!(ends(&import.skipped, "configuration.drun { display-name }"), "{:?}", import.skipped);
+        assert!(ends(&import.skipped, "listview { scrollbar }"), "{:?}", import.skipped);
+        assert!(ends(&import.skipped, "@media"), "{:?}", import.skipped);
+        // Variables that something used are not reported
+        assert!(!import.skipped.iter().any(|line| line.contains("{ accent }")), "{:?}", import.skipped);
+
+        assert!(ends(&import.approximated, "padding `8px 12px` became 10"), "{:?}", import.approximated);
+        // A non-ASCII argb value is reported rather than sliced apart
+        assert!(
+            import.approximated.iter().any(|line| line.contains("argb:fé00000")),
+            "{:?}",
+            import.approximated
+        );
+    }
+}
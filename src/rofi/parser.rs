@@ -0,0 +1,406 @@
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+
+use crate::config::{expand_home, line_column};
+
+#[derive(Debug, Clone)]
+pub struct Property {
+    // Normalized selector such as `window`, `element.selected.normal` or `configuration`
+    pub selector: String,
+    pub name: String,
+    pub value: String,
+    // `file:line`, for reporting what could not be converted
+    pub location: String,
+}
+
+// Every property of a .rasi file and the files it pulls in, in the order rofi applies them
+#[derive(Debug, Default)]
+pub struct Document {
+    pub properties: Vec<Property>,
+    pub skipped: Vec<String>,
+}
+
+impl Document {
+    pub fn load(path: &Path) -> Result<Self> {
+        let mut document = Self::default();
+        document.include(path, &mut Vec::new())?;
+        Ok(document)
+    }
+
+    // The last definition wins, like in rofi
+    pub fn get(&self, selector: &str, name: &str) -> Option<&Property> {
+        self.properties.iter().rev().find(|p| p.selector == selector && p.name == name)
+    }
+
+    fn include(&mut self, path: &Path, stack: &mut Vec<PathBuf>) -> Result<()> {
+        let canonical = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        if stack.contains(&canonical) {
+            anyhow::bail!("{} imports itself", path.display());
+        }
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        stack.push(canonical);
+        let result = Parser::new(self, path, &content, stack).run();
+        stack.pop();
+        result
+    }
+
+    pub fn apply_theme(&mut self, name: &str, relative_to: &Path) -> Result<()> {
+        let file = find_theme(name, relative_to)
+            .with_context(|| format!("cannot find rofi theme \"{}\"", name))?;
+        self.reset_theme();
+        self.include(&file, &mut Vec::new())
+    }
+
+    // `@theme` replaces everything styled so far but keeps the configuration block
+    fn reset_theme(&mut self) {
+        self.properties.retain(|p| p.selector == "configuration" || p.selector.starts_with("configuration."));
+    }
+}
+
+// Where rofi looks for `@theme "name"` and `@import "name"` that are not relative paths
+pub fn theme_dirs() -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+    if let Some(config) = dirs::config_dir() {
+        dirs.push(config.join("rofi").join("themes"));
+        dirs.push(config.join("rofi"));
+    }
+    if let Some(data) = dirs::data_dir() {
+        dirs.push(data.join("rofi").join("themes"));
+    }
+    dirs.push(PathBuf::from("/usr/local/share/rofi/themes"));
+    dirs.push(PathBuf::from("/usr/share/rofi/themes"));
+    dirs
+}
+
+pub fn find_theme(name: &str, relative_to: &Path) -> Option<PathBuf> {
+    let file = if name.ends_with(".rasi") { name.to_string() } else { format!("{}.rasi", name) };
+    let file = expand_home(Path::new(&file));
+    let base = relative_to.parent().unwrap_or(Path::new("."));
+    std::iter::once(base.to_path_buf())
+        .chain(theme_dirs())
+        .map(|dir| dir.join(&file))
+        .find(|path| path.is_file())
+}
+
+struct Parser<'a> {
+    document: &'a mut Document,
+    path: &'a Path,
+    content: String,
+    pos: usize,
+    stack: &'a mut Vec<PathBuf>,
+}
+
+impl<'a> Parser<'a> {
+    fn new(document: &'a mut Document, path: &'a Path, content: &str, stack: &'a mut Vec<PathBuf>) -> Self {
+        Self {
+            document,
+            path,
+            content: strip_comments(content),
+            pos: 0,
+            stack,
+        }
+    }
+
+    fn run(mut self) -> Result<()> {
+        loop {
+            self.skip_whitespace();
+            let Some(c) = self.peek() else {
+                return Ok(());
+            };
+            if c == '@' {
+                self.directive()?;
+            } else {
+                let start = self.pos;
+                let selector = self.take_until(&['{'])?;
+                self.pos += 1;
+                let selectors: Vec<String> = selector.split(',').map(normalize_selector).collect();
+                if selectors.iter().any(String::is_empty) {
+                    anyhow::bail!("{}: expected a selector before `{{`", self.location(start));
+                }
+                self.block(&selectors)?;
+            }
+        }
+    }
+
+    fn directive(&mut self) -> Result<()> {
+        let start = self.pos;
+        let keyword: String = self.content[self.pos..]
+            .chars()
+            .take_while(|c| *c == '@' || c.is_alphanumeric() || *c == '-')
+            .collect();
+        self.pos += keyword.len();
+
+        match keyword.as_str() {
+            "@import" | "@theme" => {
+                let argument = self.take_until(&[';', '\n'])?;
+                if self.peek() == Some(';') {
+                    self.pos += 1;
+                }
+                let name = unquote(argument.trim());
+                let Some(file) = find_theme(&name, self.path) else {
+                    anyhow::bail!("{}: cannot find {} \"{}\"", self.location(start), keyword, name);
+                };
+                if keyword == "@theme" {
+                    self.document.reset_theme();
+                }
+                self.document.include(&file, self.stack)
+            }
+            _ => {
+                // `@media` and friends depend on the screen; skip the whole block
+                self.take_until(&['{', ';'])?;
+                if self.peek() == Some('{') {
+                    self.pos += 1;
+                    self.skip_block()?;
+                } else {
+                    self.pos += 1;
+                }
+                let location = self.location(start);
+                self.document.skipped.push(format!("{}: {}", location, keyword));
+                Ok(())
+            }
+        }
+    }
+
+    fn block(&mut self, selectors: &[String]) -> Result<()> {
+        loop {
+            self.skip_whitespace();
+            match self.peek() {
+                None => anyhow::bail!("{}: missing `}}`", self.location(self.pos)),
+                Some('}') => {
+                    self.pos += 1;
+                    return Ok(());
+                }
+                Some(_) => {}
+            }
+
+            let start = self.pos;
+            let name = self.take_until(&[':', '{', '}'])?.trim().to_string();
+            match self.peek() {
+                // rofi 1.7 nests per-mode settings, e.g. `configuration { drun { ... } }`
+                Some('{') => {
+                    self.pos += 1;
+                    let nested: Vec<String> = selectors
+                        .iter()
+                        .map(|s| format!("{}.{}", s, normalize_selector(&name)))
+                        .collect();
+                    self.block(&nested)?;
+                }
+                Some(':') => {
+                    self.pos += 1;
+                    let value = self.take_until(&[';', '}'])?.trim().to_string();
+                    if self.peek() == Some(';') {
+                        self.pos += 1;
+                    }
+                    let location = self.location(start);
+                    for selector in selectors {
+                        self.document.properties.push(Property {
+                            selector: selector.clone(),
+                            name: name.clone(),
+                            value: value.clone(),
+                            location: location.clone(),
+                        });
+                    }
+                }
+                _ => anyhow::bail!("{}: expected `:` after `{}`", self.location(start), name),
+            }
+        }
+    }
+
+    fn skip_block(&mut self) -> Result<()> {
+        let mut depth = 1;
+        while depth > 0 {
+            self.take_until(&['{', '}'])?;
+            match self.peek() {
+                Some('{') => depth += 1,
+                _ => depth -= 1,
+            }
+            self.pos += 1;
+        }
+        Ok(())
+    }
+
+    // Text up to (not including) the first of `ends` outside quotes and parentheses
+    fn take_until(&mut self, ends: &[char]) -> Result<String> {
+        let start = self.pos;
+        let mut quote = None;
+        let mut depth = 0;
+        for (i, c) in self.content[start..].char_indices() {
+            match (quote, c) {
+                (Some(q), c) if c == q => quote = None,
+                (Some(_), _) => {}
+                (None, '"' | '\'') => quote = Some(c),
+                (None, '(') => depth += 1,
+                (None, ')') => depth -= 1,
+                (None, c) if depth == 0 && ends.contains(&c) => {
+                    self.pos = start + i;
+                    return Ok(self.content[start..self.pos].to_string());
+                }
+                _ => {}
+            }
+        }
+        anyhow::bail!("{}: unexpected end of file", self.location(start))
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = &self.content[self.pos..];
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.content[self.pos..].chars().next()
+    }
+
+    fn location(&self, offset: usize) -> String {
+        let (line, _) = line_column(&self.content, offset);
+        format!("{}:{}", self.path.display(), line)
+    }
+}
+
+// Comments become spaces so offsets, and with them line numbers, stay the same
+fn strip_comments(content: &str) -> String {
+    let mut out = String::with_capacity(content.len());
+    let mut chars = content.chars().peekable();
+    let mut quote = None;
+    while let Some(c) = chars.next() {
+        match (quote, c, chars.peek()) {
+            (Some(q), c, _) if c == q => {
+                quote = None;
+                out.push(c);
+            }
+            (Some(_), c, _) => out.push(c),
+            (None, '"', _) => {
+                quote = Some(c);
+                out.push(c);
+            }
+            (None, '/', Some('/')) => {
+                out.push(' ');
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        out.push('\n');
+                        break;
+                    }
+                    out.push(if c.is_ascii() { ' ' } else { c });
+                }
+            }
+            (None, '/', Some('*')) => {
+                chars.next();
+                out.push_str("  ");
+                let mut previous = ' ';
+                for c in chars.by_ref() {
+                    out.push(if c == '\n' || !c.is_ascii() { c } else { ' ' });
+                    if previous == '*' && c == '/' {
+                        break;
+                    }
+                    previous = c;
+                }
+            }
+            (None, c, _) => out.push(c),
+        }
+    }
+    out
+}
+
+// `#window`, `element selected.normal` and `element.selected.normal` all name the same widget
+fn normalize_selector(selector: &str) -> String {
+    selector
+        .trim()
+        .trim_start_matches('#')
+        .split(|c: char| c.is_whitespace() || c == '.')
+        .filter(|s| !s.is_empty())
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+pub fn unquote(value: &str) -> String {
+    let value = value.trim();
+    for quote in ['"', '\''] {
+        if let Some(inner) = value.strip_prefix(quote).and_then(|v| v.strip_suffix(quote)) {
+            return inner.to_string();
+        }
+    }
+    value.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture(name: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("src/rofi/testdata").join(name)
+    }
+
+    fn value<'a>(document: &'a Document, selector: &str, name: &str) -> &'a str {
+        &document.get(selector, name).unwrap_or_else(|| panic!("no {} {{ {} }}", selector, name)).value
+    }
+
+    #[test]
+    fn properties() {
+        let document = Document::load(&fixture("config.rasi")).unwrap();
+        assert_eq!(value(&document, "configuration", "terminal"), "\"kitty\"");
+        assert_eq!(value(&document, "*", "bg"), "var(background, #000000)");
+        assert_eq!(value(&document, "window", "padding"), "8px 12px");
+        assert_eq!(value(&document, "element.selected.normal", "text-color"), "rgba ( 255, 255, 255, 100 % )");
+        assert_eq!(value(&document, "entry", "placeholder"), "\"Search…\"");
+    }
+
+    #[test]
+    fn nested_blocks_and_selector_lists() {
+        let document = Document::load(&fixture("config.rasi")).unwrap();
+        assert_eq!(value(&document, "configuration.drun", "display-name"), "\"run\"");
+        assert_eq!(value(&document, "element-text.selected", "background-color"), "@accent");
+    }
+
+    #[test]
+    fn imports_in_place() {
+        let document = Document::load(&fixture("config.rasi")).unwrap();
+        let background = document.get("*", "background").unwrap();
+        assert_eq!(background.value, "#1d1f21");
+        assert!(background.location.ends_with("colors.rasi:3"), "{}", background.location);
+
+        // Imported properties come before the ones that follow the @import
+        let position = |name: &str| document.properties.iter().position(|p| p.name == name).unwrap();
+        assert!(position("background") < position("accent"));
+    }
+
+    #[test]
+    fn comments_keep_line_numbers() {
+        let document = Document::load(&fixture("config.rasi")).unwrap();
+        let location = &document.get("configuration", "location").unwrap();
+        assert_eq!(location.value, "2");
+        assert!(location.location.ends_with("config.rasi:7"), "{}", location.location);
+    }
+
+    #[test]
+    fn skips_media_blocks() {
+        let document = Document::load(&fixture("config.rasi")).unwrap();
+        assert_eq!(value(&document, "window", "width"), "50%");
+        assert_eq!(document.skipped.len(), 1);
+        assert!(document.skipped[0].ends_with("config.rasi:40: @media"), "{:?}", document.skipped);
+    }
+
+    #[test]
+    fn rejects_import_cycles() {
+        let error = Document::load(&fixture("loop.rasi")).unwrap_err();
+        assert!(format!("{:#}", error).contains("imports itself"), "{:#}", error);
+    }
+
+    #[test]
+    fn strips_comments_in_place() {
+        let content = "a: 1; // é note\n/* b: 2;\n ü */ c: \"// kept\";";
+        let stripped = strip_comments(content);
+        assert_eq!(stripped.len(), content.len());
+        assert_eq!(stripped.lines().count(), content.lines().count());
+        assert!(!stripped.contains("note") && !stripped.contains("b: 2"));
+        assert!(stripped.contains("c: \"// kept\""));
+    }
+
+    #[test]
+    fn selectors() {
+        assert_eq!(normalize_selector("#window"), "window");
+        assert_eq!(normalize_selector(" element selected.normal "), "element.selected.normal");
+        assert_eq!(unquote(" \"Apps:\" "), "Apps:");
+        assert_eq!(unquote("'x'"), "x");
+        assert_eq!(unquote("plain"), "plain");
+    }
+}
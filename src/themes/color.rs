@@ -0,0 +1,444 @@
+use std::fmt;
+use std::str::FromStr;
+use iced::Color;
+use schemars::JsonSchema;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use thiserror::Error;
+
+#[derive(Debug, Clone, PartialEq, Error)]
+#[error("invalid color `{input}`: {reason}")]
+pub struct ColorError {
+    pub input: String,
+    pub reason: String,
+}
+
+// A validated theme color; keeps the text it was written as so themes round-trip unchanged
+#[derive(Debug, Clone, PartialEq)]
+pub struct ThemeColor {
+    source: String,
+    color: Color,
+}
+
+impl ThemeColor {
+    pub fn color(&self) -> Color {
+        self.color
+    }
+}
+
+impl FromStr for ThemeColor {
+    type Err = ColorError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let fail = |reason: &str| ColorError {
+            input: input.to_string(),
+            reason: reason.to_string(),
+        };
+
+        let value = input.trim().to_ascii_lowercase();
+        let color = if let Some(hex) = value.strip_prefix('#') {
+            parse_hex(hex).ok_or_else(|| fail("expected #RGB, #RGBA, #RRGGBB or #RRGGBBAA"))?
+        } else if let Some(args) = function_args(&value, &["rgb", "rgba"]) {
+            parse_rgb(args).ok_or_else(|| fail("expected rgb(r, g, b) or rgba(r, g, b, a)"))?
+        } else if let Some(args) = function_args(&value, &["hsl", "hsla"]) {
+            parse_hsl(args).ok_or_else(|| fail("expected hsl(h, s%, l%) or hsla(h, s%, l%, a)"))?
+        } else if value == "transparent" {
+            Color::TRANSPARENT
+        } else if let Some(&(_, rgb)) = NAMED_COLORS.iter().find(|(name, _)| *name == value) {
+            Color::from_rgb8((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8)
+        } else if matches!(value.len(), 6 | 8) && value.chars().all(|c| c.is_ascii_hexdigit()) {
+            // Older themes sometimes left out the leading '#'
+            parse_hex(&value).ok_or_else(|| fail("expected #RRGGBB or #RRGGBBAA"))?
+        } else {
+            return Err(fail("expected a hex value, rgb(), rgba(), hsl(), hsla() or a CSS color name"));
+        };
+
+        Ok(Self {
+            source: input.trim().to_string(),
+            color,
+        })
+    }
+}
+
+impl fmt::Display for ThemeColor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+impl Serialize for ThemeColor {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.source)
+    }
+}
+
+impl<'de> Deserialize<'de> for ThemeColor {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        value.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+impl JsonSchema for ThemeColor {
+    fn schema_name() -> String {
+        "Color".to_string()
+    }
+
+    // The shape of each notation, plus "$name" for a palette variable substituted before parsing
+    fn json_schema(gen: &mut schemars::gen::SchemaGenerator) -> schemars::schema::Schema {
+        let mut schema = gen.subschema_for::<String>().into_object();
+        schema.metadata().description = Some(
+            "#RGB, #RGBA, #RRGGBB, #RRGGBBAA, rgb(), rgba(), hsl(), hsla(), a CSS color name, \
+             or $name for a palette variable"
+                .to_string(),
+        );
+        schema.string().pattern = Some(
+            r"^\s*(#?([0-9A-Fa-f]{6}|[0-9A-Fa-f]{8})|#([0-9A-Fa-f]{3,4})|[A-Za-z]+\(.*\)|[A-Za-z]+|\$.+)\s*$"
+                .to_string(),
+        );
+        schema.into()
+    }
+}
+
+fn parse_hex(hex: &str) -> Option<Color> {
+    if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    let digit = |i: usize| u8::from_str_radix(&hex[i..i + 1], 16).ok();
+    let pair = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+
+    let [r, g, b, a] = match hex.len() {
+        3 | 4 => {
+            let short = |i| digit(i).map(|d| d * 17);
+            [short(0)?, short(1)?, short(2)?, if hex.len() == 4 { short(3)? } else { 255 }]
+        }
+        6 | 8 => [pair(0)?, pair(2)?, pair(4)?, if hex.len() == 8 { pair(6)? } else { 255 }],
+        _ => return None,
+    };
+    Some(Color::from_rgba8(r, g, b, a as f32 / 255.0))
+}
+
+fn function_args<'a>(value: &'a str, names: &[&str]) -> Option<Vec<&'a str>> {
+    let open = value.find('(')?;
+    if !names.contains(&value[..open].trim()) {
+        return None;
+    }
+    let inner = value[open + 1..].strip_suffix(')')?;
+    // Both `rgb(1, 2, 3, 0.5)` and `rgb(1 2 3 / 50%)` are accepted
+    Some(
+        inner
+            .split(|c: char| c == ',' || c == '/' || c.is_whitespace())
+            .filter(|part| !part.is_empty())
+            .collect(),
+    )
+}
+
+// Plain numbers are in `0..=scale`, percentages in `0%..=100%`; the result is in 0..=1
+fn component(value: &str, scale: f32) -> Option<f32> {
+    let fraction = match value.strip_suffix('%') {
+        Some(percent) => percent.parse::<f32>().ok()? / 100.0,
+        None => value.parse::<f32>().ok()? / scale,
+    };
+    (fraction.is_finite() && (0.0..=1.0).contains(&fraction)).then_some(fraction)
+}
+
+fn alpha(args: &[&str], index: usize) -> Option<f32> {
+    match args.get(index) {
+        Some(value) => component(value, 1.0),
+        None => Some(1.0),
+    }
+}
+
+fn parse_rgb(args: Vec<&str>) -> Option<Color> {
+    if !(3..=4).contains(&args.len()) {
+        return None;
+    }
+    Some(Color::from_rgba(
+        component(args[0], 255.0)?,
+        component(args[1], 255.0)?,
+        component(args[2], 255.0)?,
+        alpha(&args, 3)?,
+    ))
+}
+
+fn parse_hsl(args: Vec<&str>) -> Option<Color> {
+    if !(3..=4).contains(&args.len()) {
+        return None;
+    }
+    let hue = args[0].trim_end_matches("deg").parse::<f32>().ok().filter(|h| h.is_finite())?.rem_euclid(360.0);
+    let saturation = component(args[1], 100.0)?;
+    let lightness = component(args[2], 100.0)?;
+
+    let chroma = (1.0 - (2.0 * lightness - 1.0).abs()) * saturation;
+    let x = chroma * (1.0 - ((hue / 60.0) % 2.0 - 1.0).abs());
+    let m = lightness - chroma / 2.0;
+    let (r, g, b) = match hue as u32 / 60 {
+        0 => (chroma, x, 0.0),
+        1 => (x, chroma, 0.0),
+        2 => (0.0, chroma, x),
+        3 => (0.0, x, chroma),
+        4 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x),
+    };
+    Some(Color::from_rgba(r + m, g + m, b + m, alpha(&args, 3)?))
+}
+
+const NAMED_COLORS: &[(&str, u32)] = &[
+    ("aliceblue", 0xf0f8ff),
+    ("antiquewhite", 0xfaebd7),
+    ("aqua", 0x00ffff),
+    ("aquamarine", 0x7fffd4),
+    ("azure", 0xf0ffff),
+    ("beige", 0xf5f5dc),
+    ("bisque", 0xffe4c4),
+    ("black", 0x000000),
+    ("blanchedalmond", 0xffebcd),
+    ("blue", 0x0000ff),
+    ("blueviolet", 0x8a2be2),
+    ("brown", 0xa52a2a),
+    ("burlywood", 0xdeb887),
+    ("cadetblue", 0x5f9ea0),
+    ("chartreuse", 0x7fff00),
+    ("chocolate", 0xd2691e),
+    ("coral", 0xff7f50),
+    ("cornflowerblue", 0x6495ed),
+    ("cornsilk", 0xfff8dc),
+    ("crimson", 0xdc143c),
+    ("cyan", 0x00ffff),
+    ("darkblue", 0x00008b),
+    ("darkcyan", 0x008b8b),
+    ("darkgoldenrod", 0xb8860b),
+    ("darkgray", 0xa9a9a9),
+    ("darkgreen", 0x006400),
+    ("darkgrey", 0xa9a9a9),
+    ("darkkhaki", 0xbdb76b),
+    ("darkmagenta", 0x8b008b),
+    ("darkolivegreen", 0x556b2f),
+    ("darkorange", 0xff8c00),
+    ("darkorchid", 0x9932cc),
+    ("darkred", 0x8b0000),
+    ("darksalmon", 0xe9967a),
+    ("darkseagreen", 0x8fbc8f),
+    ("darkslateblue", 0x483d8b),
+    ("darkslategray", 0x2f4f4f),
+    ("darkslategrey", 0x2f4f4f),
+    ("darkturquoise", 0x00ced1),
+    ("darkviolet", 0x9400d3),
+    ("deeppink", 0xff1493),
+    ("deepskyblue", 0x00bfff),
+    ("dimgray", 0x696969),
+    ("dimgrey", 0x696969),
+    ("dodgerblue", 0x1e90ff),
+    ("firebrick", 0xb22222),
+    ("floralwhite", 0xfffaf0),
+    ("forestgreen", 0x228b22),
+    ("fuchsia", 0xff00ff),
+    ("gainsboro", 0xdcdcdc),
+    ("ghostwhite", 0xf8f8ff),
+    ("gold", 0xffd700),
+    ("goldenrod", 0xdaa520),
+    ("gray", 0x808080),
+    ("green", 0x008000),
+    ("greenyellow", 0xadff2f),
+    ("grey", 0x808080),
+    ("honeydew", 0xf0fff0),
+    ("hotpink", 0xff69b4),
+    ("indianred", 0xcd5c5c),
+    ("indigo", 0x4b0082),
+    ("ivory", 0xfffff0),
+    ("khaki", 0xf0e68c),
+    ("lavender", 0xe6e6fa),
+    ("lavenderblush", 0xfff0f5),
+    ("lawngreen", 0x7cfc00),
+    ("lemonchiffon", 0xfffacd),
+    ("lightblue", 0xadd8e6),
+    ("lightcoral", 0xf08080),
+    ("lightcyan", 0xe0ffff),
+    ("lightgoldenrodyellow", 0xfafad2),
+    ("lightgray", 0xd3d3d3),
+    ("lightgreen", 0x90ee90),
+    ("lightgrey", 0xd3d3d3),
+    ("lightpink", 0xffb6c1),
+    ("lightsalmon", 0xffa07a),
+    ("lightseagreen", 0x20b2aa),
+    ("lightskyblue", 0x87cefa),
+    ("lightslategray", 0x778899),
+    ("lightslategrey", 0x778899),
+    ("lightsteelblue", 0xb0c4de),
+    ("lightyellow", 0xffffe0),
+    ("lime", 0x00ff00),
+    ("limegreen", 0x32cd32),
+    ("linen", 0xfaf0e6),
+    ("magenta", 0xff00ff),
+    ("maroon", 0x800000),
+    ("mediumaquamarine", 0x66cdaa),
+    ("mediumblue", 0x0000cd),
+    ("mediumorchid", 0xba55d3),
+    ("mediumpurple", 0x9370db),
+    ("mediumseagreen", 0x3cb371),
+    ("mediumslateblue", 0x7b68ee),
+    ("mediumspringgreen", 0x00fa9a),
+    ("mediumturquoise", 0x48d1cc),
+    ("mediumvioletred", 0xc71585),
+    ("midnightblue", 0x191970),
+    ("mintcream", 0xf5fffa),
+    ("mistyrose", 0xffe4e1),
+    ("moccasin", 0xffe4b5),
+    ("navajowhite", 0xffdead),
+    ("navy", 0x000080),
+    ("oldlace", 0xfdf5e6),
+    ("olive", 0x808000),
+    ("olivedrab", 0x6b8e23),
+    ("orange", 0xffa500),
+    ("orangered", 0xff4500),
+    ("orchid", 0xda70d6),
+    ("palegoldenrod", 0xeee8aa),
+    ("palegreen", 0x98fb98),
+    ("paleturquoise", 0xafeeee),
+    ("palevioletred", 0xdb7093),
+    ("papayawhip", 0xffefd5),
+    ("peachpuff", 0xffdab9),
+    ("peru", 0xcd853f),
+    ("pink", 0xffc0cb),
+    ("plum", 0xdda0dd),
+    ("powderblue", 0xb0e0e6),
+    ("purple", 0x800080),
+    ("rebeccapurple", 0x663399),
+    ("red", 0xff0000),
+    ("rosybrown", 0xbc8f8f),
+    ("royalblue", 0x4169e1),
+    ("saddlebrown", 0x8b4513),
+    ("salmon", 0xfa8072),
+    ("sandybrown", 0xf4a460),
+    ("seagreen", 0x2e8b57),
+    ("seashell", 0xfff5ee),
+    ("sienna", 0xa0522d),
+    ("silver", 0xc0c0c0),
+    ("skyblue", 0x87ceeb),
+    ("slateblue", 0x6a5acd),
+    ("slategray", 0x708090),
+    ("slategrey", 0x708090),
+    ("snow", 0xfffafa),
+    ("springgreen", 0x00ff7f),
+    ("steelblue", 0x4682b4),
+    ("tan", 0xd2b48c),
+    ("teal", 0x008080),
+    ("thistle", 0xd8bfd8),
+    ("tomato", 0xff6347),
+    ("turquoise", 0x40e0d0),
+    ("violet", 0xee82ee),
+    ("wheat", 0xf5deb3),
+    ("white", 0xffffff),
+    ("whitesmoke", 0xf5f5f5),
+    ("yellow", 0xffff00),
+    ("yellowgreen", 0x9acd32),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rgba(input: &str) -> [u8; 4] {
+        let color = input.parse::<ThemeColor>().unwrap_or_else(|e| panic!("{}", e)).color();
+        [color.r, color.g, color.b, color.a].map(|c| (c * 255.0).round() as u8)
+    }
+
+    fn rejects(input: &str) {
+        assert!(input.parse::<ThemeColor>().is_err(), "`{}` should not parse", input);
+    }
+
+    #[test]
+    fn hex() {
+        assert_eq!(rgba("#fff"), [255, 255, 255, 255]);
+        assert_eq!(rgba("#f008"), [255, 0, 0, 136]);
+        assert_eq!(rgba("#1a2b3c"), [0x1a, 0x2b, 0x3c, 255]);
+        assert_eq!(rgba("#1A2B3C80"), [0x1a, 0x2b, 0x3c, 0x80]);
+        assert_eq!(rgba("1a2b3c"), [0x1a, 0x2b, 0x3c, 255]);
+        assert_eq!(rgba("  #000  "), [0, 0, 0, 255]);
+    }
+
+    #[test]
+    fn rgb() {
+        assert_eq!(rgba("rgb(255, 128, 0)"), [255, 128, 0, 255]);
+        assert_eq!(rgba("rgba(255, 128, 0, 0.5)"), [255, 128, 0, 128]);
+        assert_eq!(rgba("rgb(100%, 0%, 50%)"), [255, 0, 128, 255]);
+        assert_eq!(rgba("rgb(255 128 0 / 50%)"), [255, 128, 0, 128]);
+        assert_eq!(rgba("RGBA(0, 0, 0, 1)"), [0, 0, 0, 255]);
+    }
+
+    #[test]
+    fn hsl() {
+        assert_eq!(rgba("hsl(0, 100%, 50%)"), [255, 0, 0, 255]);
+        assert_eq!(rgba("hsl(120deg, 100%, 25%)"), [0, 128, 0, 255]);
+        assert_eq!(rgba("hsl(-120, 100%, 50%)"), [0, 0, 255, 255]);
+        assert_eq!(rgba("hsla(240, 100%, 50%, 0.5)"), [0, 0, 255, 128]);
+        assert_eq!(rgba("hsl(60 100% 50% / 25%)"), [255, 255, 0, 64]);
+    }
+
+    #[test]
+    fn named() {
+        assert_eq!(rgba("rebeccapurple"), [0x66, 0x33, 0x99, 255]);
+        assert_eq!(rgba("White"), [255, 255, 255, 255]);
+        assert_eq!(rgba("transparent")[3], 0);
+    }
+
+    #[test]
+    fn keeps_source() {
+        let color: ThemeColor = " #ABC ".parse().unwrap();
+        assert_eq!(color.to_string(), "#ABC");
+    }
+
+    #[test]
+    fn rejects_bad_lengths() {
+        for input in ["#", "#f", "#ff", "#fffff", "#fffffff", "#fffffffff", "fff", "1a2b3c4"] {
+            rejects(input);
+        }
+    }
+
+    #[test]
+    fn rejects_bad_digits() {
+        rejects("#ggg");
+        rejects("#12345z");
+        rejects("#+1234");
+    }
+
+    #[test]
+    fn rejects_out_of_range() {
+        rejects("rgb(256, 0, 0)");
+        rejects("rgb(-1, 0, 0)");
+        rejects("rgba(0, 0, 0, 1.5)");
+        rejects("rgb(101%, 0%, 0%)");
+        rejects("hsl(0, 120%, 50%)");
+        rejects("hsl(0, 50%, -5%)");
+    }
+
+    #[test]
+    fn rejects_wrong_arguments() {
+        rejects("rgb(1, 2)");
+        rejects("rgb(1, 2, 3, 4, 5)");
+        rejects("rgb(1, 2, 3");
+        rejects("hsl()");
+        rejects("rgb(a, b, c)");
+        rejects("cmyk(0, 0, 0, 0)");
+    }
+
+    #[test]
+    fn rejects_non_finite() {
+        for input in [
+            "hsl(nan, 50%, 50%)",
+            "hsl(inf, 50%, 50%)",
+            "hsl(-infinity, 50%, 50%)",
+            "rgb(nan, 0, 0)",
+            "rgb(inf, 0, 0)",
+            "rgba(0, 0, 0, nan)",
+        ] {
+            rejects(input);
+        }
+    }
+
+    #[test]
+    fn rejects_non_ascii() {
+        for input in ["#ffé", "#fféfff", "rgb(１, 2, 3)", "rëd", "ｒｇｂ(1, 2, 3)", "é"] {
+            rejects(input);
+        }
+    }
+}
@@ -0,0 +1,114 @@
+use iced::font::{Family, Weight};
+use iced::Font;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+
+use super::ThemeColor;
+
+// Styling for one part of the window; anything left out falls back to the flat theme fields
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(default, deny_unknown_fields)]
+pub struct ElementStyle {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub background: Option<ThemeColor>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text_color: Option<ThemeColor>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub border_color: Option<ThemeColor>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub border_width: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub border_radius: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub padding: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub spacing: Option<f32>,
+    #[serde(skip_serializing_if = "FontSpec::is_empty")]
+    pub font: FontSpec,
+}
+
+impl ElementStyle {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(default, deny_unknown_fields)]
+pub struct FontSpec {
+    // "monospace", "sans-serif", "serif", or the name of an installed or bundled font
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub family: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub size: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub weight: Option<FontWeight>,
+}
+
+impl FontSpec {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    pub fn apply(&self, base: Font) -> Font {
+        let mut font = base;
+        if let Some(family) = &self.family {
+            let (family, monospaced) = match family.to_ascii_lowercase().as_str() {
+                "monospace" => (Family::Monospace, true),
+                "sans-serif" | "sans" => (Family::SansSerif, false),
+                "serif" => (Family::Serif, false),
+                _ => (Family::Name(intern(family)), false),
+            };
+            font.family = family;
+            font.monospaced = monospaced;
+        }
+        if let Some(weight) = self.weight {
+            font.weight = weight.into();
+        }
+        font
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum FontWeight {
+    Thin,
+    ExtraLight,
+    Light,
+    Normal,
+    Medium,
+    Semibold,
+    Bold,
+    ExtraBold,
+    Black,
+}
+
+impl From<FontWeight> for Weight {
+    fn from(weight: FontWeight) -> Self {
+        match weight {
+            FontWeight::Thin => Weight::Thin,
+            FontWeight::ExtraLight => Weight::ExtraLight,
+            FontWeight::Light => Weight::Light,
+            FontWeight::Normal => Weight::Normal,
+            FontWeight::Medium => Weight::Medium,
+            FontWeight::Semibold => Weight::Semibold,
+            FontWeight::Bold => Weight::Bold,
+            FontWeight::ExtraBold => Weight::ExtraBold,
+            FontWeight::Black => Weight::Black,
+        }
+    }
+}
+
+// iced wants &'static family names; keep one copy of each so theme reloads don't keep leaking
+fn intern(name: &str) -> &'static str {
+    static NAMES: Mutex<Vec<&'static str>> = Mutex::new(Vec::new());
+
+    let mut names = NAMES.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(existing) = names.iter().find(|n| **n == name) {
+        return existing;
+    }
+    let leaked: &'static str = Box::leak(name.to_string().into_boxed_str());
+    names.push(leaked);
+    leaked
+}
@@ -0,0 +1,361 @@
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use super::ThemeColor;
+
+// Theme name that always follows the current pywal colors
+pub const PYWAL: &str = "pywal";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Format {
+    Pywal,
+    Xresources,
+    Base16,
+}
+
+impl Format {
+    pub fn detect(path: &Path) -> Self {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("json") => Format::Pywal,
+            Some("yaml" | "yml") => Format::Base16,
+            _ => Format::Xresources,
+        }
+    }
+
+    pub fn default_path(self) -> Option<PathBuf> {
+        match self {
+            Format::Pywal => Some(pywal_path()),
+            Format::Xresources => dirs::home_dir().map(|home| home.join(".Xresources")),
+            Format::Base16 => None,
+        }
+    }
+}
+
+pub fn pywal_path() -> PathBuf {
+    dirs::cache_dir()
+        .unwrap_or_else(|| PathBuf::from("~/.cache"))
+        .join("wal")
+        .join("colors.json")
+}
+
+// Flat theme fields and the palette entries they are set from
+const FIELDS: &[(&str, &str)] = &[
+    ("background_color", "background"),
+    ("text_color", "foreground"),
+    ("selected_background_color", "accent"),
+    ("selected_text_color", "accent_foreground"),
+    ("border_color", "border"),
+];
+
+// Reads a color scheme and returns the TOML of a theme that extends the default theme
+// for its sizes. The colors are written out in full rather than left to the default
+// theme's variables, since a user's own default.toml may not use them
+pub fn import(format: Format, path: &Path, name: Option<&str>) -> Result<String> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    let scheme = match format {
+        Format::Pywal => pywal(&content),
+        Format::Xresources => xresources(&content),
+        Format::Base16 => base16(&content),
+    }
+    .with_context(|| format!("Failed to import {}", path.display()))?;
+
+    let name = name.map(str::to_string).or(scheme.name).unwrap_or_else(|| {
+        path.file_stem().map_or_else(|| "imported".to_string(), |s| s.to_string_lossy().into_owned())
+    });
+
+    let mut table = toml::Table::new();
+    table.insert("name".to_string(), toml::Value::String(name));
+    table.insert("extends".to_string(), toml::Value::String("default".to_string()));
+    for (field, entry) in FIELDS {
+        let color = resolve(&scheme.palette, entry).with_context(|| format!("no `{}` color", entry))?;
+        table.insert(field.to_string(), toml::Value::String(color));
+    }
+    table.insert(
+        "palette".to_string(),
+        toml::Value::Table(scheme.palette.into_iter().map(|(k, v)| (k, toml::Value::String(v))).collect()),
+    );
+    Ok(format!("# Imported from {}\n{}", path.display(), toml::to_string(&table)?))
+}
+
+// A palette entry's color, following `$name` references to other entries
+fn resolve(palette: &[(String, String)], name: &str) -> Option<String> {
+    let mut value = &palette.iter().find(|(key, _)| key == name)?.1;
+    for _ in 0..palette.len() {
+        match value.strip_prefix('$') {
+            Some(next) => value = &palette.iter().find(|(key, _)| key == next)?.1,
+            None => return Some(value.clone()),
+        }
+    }
+    None
+}
+
+struct Scheme {
+    name: Option<String>,
+    // Ordered palette entries; the first five are the ones the theme's colors come from
+    palette: Vec<(String, String)>,
+}
+
+// The terminal palettes agree on color4 being blue and color8 a muted gray
+fn terminal_palette(background: String, foreground: String, colors: Vec<String>) -> Result<Vec<(String, String)>> {
+    if colors.len() < 9 {
+        anyhow::bail!("expected at least color0 through color8, found {} colors", colors.len());
+    }
+    let mut palette = vec![
+        ("background".to_string(), background),
+        ("foreground".to_string(), foreground),
+        ("accent".to_string(), colors[4].clone()),
+        ("accent_foreground".to_string(), "$background".to_string()),
+        ("border".to_string(), colors[8].clone()),
+    ];
+    palette.extend(colors.into_iter().enumerate().map(|(i, c)| (format!("color{}", i), c)));
+    Ok(palette)
+}
+
+fn pywal(content: &str) -> Result<Scheme> {
+    let json: serde_json::Value = serde_json::from_str(content)?;
+    let color = |section: &str, key: &str| -> Result<String> {
+        let value = json[section][key]
+            .as_str()
+            .with_context(|| format!("missing `{}.{}`", section, key))?;
+        normalize(value)
+    };
+
+    let mut colors = Vec::new();
+    for i in 0..16 {
+        match color("colors", &format!("color{}", i)) {
+            Ok(c) => colors.push(c),
+            Err(_) => break,
+        }
+    }
+    let palette = terminal_palette(color("special", "background")?, color("special", "foreground")?, colors)?;
+    Ok(Scheme { name: Some("wal".to_string()), palette })
+}
+
+fn xresources(content: &str) -> Result<Scheme> {
+    let mut defines = HashMap::new();
+    let mut values = HashMap::new();
+
+    for line in content.lines().map(str::trim) {
+        if let Some(define) = line.strip_prefix("#define") {
+            let mut parts = define.split_whitespace();
+            if let (Some(key), Some(value)) = (parts.next(), parts.next()) {
+                defines.insert(key.to_string(), value.to_string());
+            }
+            continue;
+        }
+        if line.starts_with('!') || line.starts_with('#') {
+            continue;
+        }
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        // `*.color4`, `URxvt*background` and `*foreground` all count; the last one wins
+        let key = key.trim().rsplit(['.', '*']).next().unwrap_or_default().to_string();
+        let value = value.trim();
+        let value = defines.get(value).map_or(value, String::as_str);
+        values.insert(key, value.to_string());
+    }
+
+    let color = |key: &str| -> Result<String> {
+        normalize(values.get(key).with_context(|| format!("no `{}` resource found", key))?)
+    };
+    let mut colors = Vec::new();
+    for i in 0..16 {
+        match color(&format!("color{}", i)) {
+            Ok(c) => colors.push(c),
+            Err(_) => break,
+        }
+    }
+    let palette = terminal_palette(color("background")?, color("foreground")?, colors)?;
+    Ok(Scheme { name: Some("xresources".to_string()), palette })
+}
+
+fn base16(content: &str) -> Result<Scheme> {
+    let mut values = HashMap::new();
+    // Both the flat scheme format and the newer one with a nested `palette:` block are
+    // just `key: value` lines once indentation is ignored
+    for line in content.lines().map(str::trim) {
+        if line.starts_with('#') {
+            continue;
+        }
+        if let Some((key, value)) = line.split_once(':') {
+            values.insert(key.trim().to_string(), yaml_scalar(value));
+        }
+    }
+
+    let mut bases = Vec::new();
+    for i in 0..16 {
+        let key = format!("base{:02X}", i);
+        let value = values
+            .get(&key)
+            .or_else(|| values.get(&key.to_lowercase()))
+            .with_context(|| format!("missing `{}`", key))?;
+        bases.push((key, normalize(value)?));
+    }
+
+    let base = |i: usize| bases[i].1.clone();
+    let mut palette = vec![
+        ("background".to_string(), base(0x0)),
+        ("foreground".to_string(), base(0x5)),
+        ("accent".to_string(), base(0xD)),
+        ("accent_foreground".to_string(), "$background".to_string()),
+        ("border".to_string(), base(0x3)),
+    ];
+    palette.extend(bases);
+
+    let name = values
+        .get("scheme")
+        .or_else(|| values.get("name"))
+        .filter(|n| !n.is_empty())
+        .map(|n| slug(n));
+    Ok(Scheme { name, palette })
+}
+
+fn yaml_scalar(value: &str) -> String {
+    let value = value.trim();
+    for quote in ['"', '\''] {
+        if let Some(rest) = value.strip_prefix(quote) {
+            return rest.split(quote).next().unwrap_or_default().to_string();
+        }
+    }
+    value.split(" #").next().unwrap_or_default().trim().to_string()
+}
+
+// Schemes write colors as `1d1f21`, `#1d1f21` or X11's `rgb:1d/1f/21`
+fn normalize(value: &str) -> Result<String> {
+    let value = value.trim();
+    let hex = if let Some(rgb) = value.strip_prefix("rgb:") {
+        let parts: Vec<&str> = rgb.split('/').collect();
+        if parts.len() != 3 {
+            anyhow::bail!("invalid color `{}`", value);
+        }
+        // Components have one to four hex digits; `f` means `ff`, `ffff` is cut to `ff`
+        let channel = |p: &str| if p.len() == 1 { p.repeat(2) } else { p.get(..2).unwrap_or(p).to_string() };
+        format!("#{}{}{}", channel(parts[0]), channel(parts[1]), channel(parts[2]))
+    } else if value.starts_with('#') {
+        value.to_string()
+    } else {
+        format!("#{}", value)
+    };
+    hex.parse::<ThemeColor>()?;
+    Ok(hex.to_lowercase())
+}
+
+fn slug(name: &str) -> String {
+    let slug: String = name
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect();
+    slug.split('-').filter(|s| !s.is_empty()).collect::<Vec<_>>().join("-")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::scratch::ScratchDir;
+    use crate::themes::Theme;
+
+    const BASE16: &str = "\
+scheme: \"Test Scheme\"
+base00: \"101010\"
+base01: \"111111\"
+base02: \"121212\"
+base03: \"131313\"
+base04: \"141414\"
+base05: \"151515\"
+base06: \"161616\"
+base07: \"171717\"
+base08: \"181818\"
+base09: \"191919\"
+base0A: \"1a1a1a\"
+base0B: \"1b1b1b\"
+base0C: \"1c1c1c\"
+base0D: \"1d1d1d\"
+base0E: \"1e1e1e\"
+base0F: \"1f1f1f\"
+";
+
+    const XRESOURCES: &str = "\
+#define bg #202020
+*background: bg
+*foreground: #212121
+*.color0: #300000
+*.color1: #310000
+*.color2: #320000
+*.color3: #330000
+*.color4: rgb:34/00/00
+*.color5: #350000
+*.color6: #360000
+*.color7: #370000
+*.color8: #380000
+";
+
+    // The default.toml older versions wrote for every user, with its colors spelled out
+    const OLD_DEFAULT: &str = "\
+name = \"default\"
+background_color = \"#000000\"
+text_color = \"#ffffff\"
+selected_background_color = \"#0000ff\"
+selected_text_color = \"#ffffff\"
+border_color = \"#808080\"
+border_width = 1.0
+border_radius = 4.0
+padding = 10.0
+";
+
+    // Imports `scheme` and loads the result next to a user default.toml of the old kind
+    fn load(format: Format, file: &str, scheme: &str) -> Theme {
+        let dir = ScratchDir::new(&format!("import-{}", file));
+        let path = dir.join(file);
+        std::fs::write(&path, scheme).unwrap();
+        std::fs::write(dir.join("default.toml"), OLD_DEFAULT).unwrap();
+
+        let content = import(format, &path, Some("imported")).unwrap();
+        Theme::parse(&path, &content, "imported", &[dir.to_path_buf()]).unwrap()
+    }
+
+    fn colors(theme: &Theme) -> [String; 5] {
+        [
+            &theme.background_color,
+            &theme.text_color,
+            &theme.selected_background_color,
+            &theme.selected_text_color,
+            &theme.border_color,
+        ]
+        .map(ToString::to_string)
+    }
+
+    #[test]
+    fn base16_colors_win_over_a_user_default() {
+        let theme = load(Format::Base16, "scheme.yaml", BASE16);
+        assert_eq!(colors(&theme), ["#101010", "#151515", "#1d1d1d", "#101010", "#131313"]);
+        // Sizes still come from the theme it extends
+        assert_eq!(theme.border_width, 1.0);
+    }
+
+    #[test]
+    fn xresources_colors_win_over_a_user_default() {
+        let theme = load(Format::Xresources, "Xresources", XRESOURCES);
+        assert_eq!(colors(&theme), ["#202020", "#212121", "#340000", "#202020", "#380000"]);
+    }
+
+    #[test]
+    fn pywal_colors_win_over_a_user_default() {
+        let colors_json: Vec<String> = (0..16).map(|i| format!("\"color{}\": \"#4000{:02x}\"", i, i)).collect();
+        let json = format!(
+            "{{\"special\": {{\"background\": \"#404040\", \"foreground\": \"#414141\"}}, \"colors\": {{{}}}}}",
+            colors_json.join(", ")
+        );
+        let theme = load(Format::Pywal, "colors.json", &json);
+        assert_eq!(colors(&theme), ["#404040", "#414141", "#400004", "#404040", "#400008"]);
+    }
+
+    #[test]
+    fn rejects_short_palettes() {
+        assert!(xresources("*background: #000\n*foreground: #fff\n*.color0: #000\n").is_err());
+        assert!(base16("base00: \"000000\"\n").is_err());
+    }
+}
@@ -1,99 +1,473 @@
+mod color;
+mod element;
+pub mod import;
+mod palette;
+
+use schemars::schema::{ObjectValidation, RootSchema, Schema, SchemaObject};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use iced::Color;
-use std::path::PathBuf;
-use anyhow::Result;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use anyhow::{Context, Result};
+
+use crate::config::{expand_home, line_column, merge_tables};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+pub use color::ThemeColor;
+pub use element::ElementStyle;
+pub use palette::{ElementPalette, Palette};
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct Theme {
     pub name: String,
-    pub background_color: String,
-    pub text_color: String,
-    pub selected_background_color: String,
-    pub selected_text_color: String,
-    pub border_color: String,
+    pub background_color: ThemeColor,
+    pub text_color: ThemeColor,
+    pub selected_background_color: ThemeColor,
+    pub selected_text_color: ThemeColor,
+    pub border_color: ThemeColor,
     pub border_width: f32,
     pub border_radius: f32,
     pub padding: f32,
+    // Name of a theme whose fields this one starts from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub extends: Option<String>,
+    // Named colors that fields can reference as "$name"
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub palette: BTreeMap<String, String>,
+    // Font files (.ttf/.otf) to load, relative to the theme file that lists them
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub font_files: Vec<PathBuf>,
+    #[serde(default, skip_serializing_if = "ElementStyle::is_empty")]
+    pub window: ElementStyle,
+    #[serde(default, skip_serializing_if = "ElementStyle::is_empty")]
+    pub input: ElementStyle,
+    #[serde(default, skip_serializing_if = "ElementStyle::is_empty")]
+    pub prompt: ElementStyle,
+    #[serde(default, skip_serializing_if = "ElementStyle::is_empty")]
+    pub list: ElementStyle,
+    #[serde(default, skip_serializing_if = "ElementStyle::is_empty")]
+    pub row: ElementStyle,
+    #[serde(default, skip_serializing_if = "ElementStyle::is_empty")]
+    pub selected_row: ElementStyle,
+    #[serde(default, skip_serializing_if = "ElementStyle::is_empty")]
+    pub alternate_row: ElementStyle,
+    #[serde(default, skip_serializing_if = "ElementStyle::is_empty")]
+    pub status_bar: ElementStyle,
+    // Files the theme was read from, its own first and then those it extends
+    #[serde(skip)]
+    pub files: Vec<PathBuf>,
 }
 
 impl Default for Theme {
     fn default() -> Self {
-        Self {
-            name: "default".to_string(),
-            background_color: "#2E344000".to_string(),
-            text_color: "#ECEFF4FF".to_string(),
-            selected_background_color: "#5E81ACFF".to_string(),
-            selected_text_color: "#FFFFFFFF".to_string(),
-            border_color: "#4C566A00".to_string(),
-            border_width: 0.0,
-            border_radius: 0.0,
-            padding: 10.0,
-        }
+        let content = builtin("default").expect("default theme is bundled");
+        Self::parse(Path::new("<built-in default>"), content, "default", &[])
+            .expect("bundled default theme is valid")
     }
 }
 
+// Bundled with the binary so they exist on a fresh install; user files of the same name win
+const BUILTIN_THEMES: &[(&str, &str)] = &[
+    ("default", include_str!("../../config/themes/default.toml")),
+    ("ketputin", include_str!("../../config/themes/ketputin.toml")),
+    ("matrix", include_str!("../../config/themes/matrix.toml")),
+    ("ocean", include_str!("../../config/themes/ocean.toml")),
+];
+
+fn builtin(name: &str) -> Option<&'static str> {
+    BUILTIN_THEMES.iter().find(|(n, _)| *n == name).map(|(_, content)| *content)
+}
+
+pub enum Source {
+    File(PathBuf),
+    Builtin(&'static str),
+    // Generated from pywal's colors.json each time it is loaded
+    Pywal(PathBuf),
+}
+
 impl Theme {
-    pub fn load(name: &str) -> Result<Self> {
-        let theme_dir = dirs::config_dir()
+    pub fn dir() -> PathBuf {
+        dirs::config_dir()
             .map(|d| d.join("5menu").join("themes"))
-            .unwrap_or_else(|| PathBuf::from("config/themes"));
-        
-        let theme_file = theme_dir.join(format!("{}.toml", name));
-        
-        if !theme_file.exists() {
-            if name == "default" {
-                let default_theme = Self::default();
-                std::fs::create_dir_all(&theme_dir)?;
-                let toml = toml::to_string_pretty(&default_theme)?;
-                std::fs::write(&theme_file, toml)?;
-                return Ok(default_theme);
+            .unwrap_or_else(|| PathBuf::from("config/themes"))
+    }
+
+    // Directories searched for `<name>.toml`, most important first: `theme_paths` from
+    // the config, the user config dir, $XDG_DATA_HOME, then each $XDG_DATA_DIRS entry
+    pub fn search_dirs(theme_paths: &[PathBuf]) -> Vec<PathBuf> {
+        let mut dirs: Vec<PathBuf> = theme_paths.iter().map(|p| expand_home(p)).collect();
+        dirs.push(Self::dir());
+        if let Some(data_home) = dirs::data_dir() {
+            dirs.push(data_home.join("5menu").join("themes"));
+        }
+        let data_dirs = std::env::var("XDG_DATA_DIRS")
+            .ok()
+            .filter(|v| !v.is_empty())
+            .unwrap_or_else(|| "/usr/local/share:/usr/share".to_string());
+        for dir in std::env::split_paths(&data_dirs).filter(|d| d.is_absolute()) {
+            dirs.push(dir.join("5menu").join("themes"));
+        }
+
+        let mut unique = Vec::new();
+        for dir in dirs {
+            if !unique.contains(&dir) {
+                unique.push(dir);
             }
-            anyhow::bail!("Theme {} not found", name);
         }
+        unique
+    }
 
-        let content = std::fs::read_to_string(theme_file)?;
-        Ok(toml::from_str(&content)?)
+    // Names containing a '/' or ending in .toml are paths, handy while developing a theme
+    pub fn is_path(name: &str) -> bool {
+        name.contains('/') || name.ends_with(".toml")
     }
 
-    pub fn parse_color(&self, hex: &str) -> Color {
-        let hex = hex.trim_start_matches('#');
-        let r = u8::from_str_radix(&hex[0..2], 16).unwrap_or(0);
-        let g = u8::from_str_radix(&hex[2..4], 16).unwrap_or(0);
-        let b = u8::from_str_radix(&hex[4..6], 16).unwrap_or(0);
-        let a = if hex.len() >= 8 {
-            u8::from_str_radix(&hex[6..8], 16).unwrap_or(255)
-        } else {
-            255
-        };
-        Color::from_rgba(
-            r as f32 / 255.0,
-            g as f32 / 255.0,
-            b as f32 / 255.0,
-            a as f32 / 255.0,
-        )
+    pub fn locate(name: &str, dirs: &[PathBuf]) -> Option<Source> {
+        if Self::is_path(name) {
+            let path = expand_home(Path::new(name));
+            return path.exists().then_some(Source::File(path));
+        }
+        dirs.iter()
+            .map(|dir| dir.join(format!("{}.toml", name)))
+            .find(|path| path.exists())
+            .map(Source::File)
+            .or_else(|| {
+                let wal = import::pywal_path();
+                (name == import::PYWAL && wal.exists()).then_some(Source::Pywal(wal))
+            })
+            .or_else(|| builtin(name).map(Source::Builtin))
     }
 
-    pub fn get_available_themes() -> Vec<String> {
-        let theme_dir = dirs::config_dir()
-            .map(|d| d.join("5menu").join("themes"))
-            .unwrap_or_else(|| PathBuf::from("config/themes"));
-        
-        let mut themes = vec!["default".to_string()];
-        
-        if let Ok(entries) = std::fs::read_dir(&theme_dir) {
-            for entry in entries.filter_map(Result::ok) {
-                if let Some(name) = entry.file_name().to_str() {
-                    if name.ends_with(".toml") {
-                        let theme_name = name.trim_end_matches(".toml").to_string();
-                        if theme_name != "default" {
-                            themes.push(theme_name);
+    // The TOML a theme is defined by, as a starting point for custom themes
+    pub fn source(name: &str, theme_paths: &[PathBuf]) -> Result<String> {
+        match Self::locate(name, &Self::search_dirs(theme_paths)) {
+            Some(Source::File(path)) => Ok(std::fs::read_to_string(path)?),
+            Some(Source::Builtin(content)) => Ok(content.to_string()),
+            Some(Source::Pywal(path)) => import::import(import::Format::Pywal, &path, Some(name)),
+            None => anyhow::bail!("Theme {} not found", name),
+        }
+    }
+
+    pub fn load(name: &str, theme_paths: &[PathBuf]) -> Result<Self> {
+        let dirs = Self::search_dirs(theme_paths);
+        match Self::locate(name, &dirs) {
+            Some(Source::File(path)) => Self::load_file(&path, &dirs),
+            Some(Source::Builtin(content)) => {
+                Self::parse(Path::new(&format!("<built-in {}>", name)), content, name, &dirs)
+            }
+            Some(Source::Pywal(path)) => {
+                let content = import::import(import::Format::Pywal, &path, Some(name))?;
+                Self::parse(&path, &content, name, &dirs)
+            }
+            None => anyhow::bail!("Theme {} not found", name),
+        }
+    }
+
+    fn load_file(path: &Path, dirs: &[PathBuf]) -> Result<Self> {
+        let name = path.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default();
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Theme {} not found", name))?;
+
+        // Parents next to the file are found first, so a theme under development can
+        // extend a sibling without installing either
+        let mut dirs = dirs.to_vec();
+        if let Some(parent) = path.parent() {
+            dirs.insert(0, parent.to_path_buf());
+        }
+        Self::parse(path, &content, &name, &dirs)
+    }
+
+    // Reports the offending field and line instead of failing somewhere in the UI
+    fn parse(path: &Path, content: &str, name: &str, dirs: &[PathBuf]) -> Result<Self> {
+        let mut layers = Vec::new();
+        let mut table = resolve(path, content, &mut vec![name.to_string()], dirs, &mut layers)?;
+        substitute_variables(&mut table)?;
+
+        let theme: Result<Self> = serde_path_to_error::deserialize(toml::Value::Table(table)).map_err(|e| {
+            let field = e.path().to_string();
+            let message = e.into_inner().message().to_string();
+            // The nearest layer that spells the key out is the one whose value won
+            let found = layers
+                .iter()
+                .find_map(|(layer, content)| find_key(content, &field).map(|position| (layer, position)));
+            match found {
+                Some((layer, (line, column))) => anyhow::anyhow!(
+                    "{}:{}:{}: invalid value for `{}`: {}", layer.display(), line, column, field, message
+                ),
+                None => anyhow::anyhow!("{}: invalid value for `{}`: {}", path.display(), field, message),
+            }
+        });
+        let files = layers.into_iter().map(|(layer, _)| layer).filter(|layer| layer.is_file()).collect();
+        Ok(Self { files, ..theme? })
+    }
+
+    // The schema of a theme file rather than of a loaded theme: a file that extends another
+    // may leave out any field, and `name` defaults to the file name
+    pub fn file_schema() -> RootSchema {
+        let mut root = schemars::schema_for!(Theme);
+        let object = root.schema.object();
+        let required: BTreeSet<String> = std::mem::take(&mut object.required);
+        let own = Schema::Object(SchemaObject {
+            object: Some(Box::new(ObjectValidation {
+                required: required.into_iter().filter(|field| field != "name").collect(),
+                ..Default::default()
+            })),
+            ..Default::default()
+        });
+        let extends = Schema::Object(SchemaObject {
+            object: Some(Box::new(ObjectValidation {
+                required: BTreeSet::from(["extends".to_string()]),
+                ..Default::default()
+            })),
+            ..Default::default()
+        });
+        root.schema.subschemas().any_of = Some(vec![extends, own]);
+        root
+    }
+
+    pub fn get_available_themes(theme_paths: &[PathBuf]) -> Vec<String> {
+        let mut themes: Vec<String> = BUILTIN_THEMES.iter().map(|(name, _)| name.to_string()).collect();
+        if import::pywal_path().exists() {
+            themes.push(import::PYWAL.to_string());
+        }
+
+        for dir in Self::search_dirs(theme_paths) {
+            if let Ok(entries) = std::fs::read_dir(dir) {
+                for entry in entries.filter_map(Result::ok) {
+                    if let Some(name) = entry.file_name().to_str() {
+                        if let Some(theme_name) = name.strip_suffix(".toml") {
+                            themes.push(theme_name.to_string());
                         }
                     }
                 }
             }
         }
-        
+
         themes.sort();
+        themes.dedup();
         themes
     }
 }
+
+// Merges the `extends` chain, parents first, so children can override palette entries too.
+// Every layer read along the way is added to `layers`, the child before its parents
+fn resolve(
+    path: &Path,
+    content: &str,
+    chain: &mut Vec<String>,
+    dirs: &[PathBuf],
+    layers: &mut Vec<(PathBuf, String)>,
+) -> Result<toml::Table> {
+    layers.push((path.to_path_buf(), content.to_string()));
+    let mut table: toml::Table = toml::from_str(content).map_err(|e| {
+        let (line, column) = line_column(content, e.span().map_or(0, |span| span.start));
+        anyhow::anyhow!("{}:{}:{}: {}", path.display(), line, column, e.message())
+    })?;
+    anchor_font_files(path, &mut table);
+
+    if let Some(parent) = table.get("extends").and_then(toml::Value::as_str).map(str::to_string) {
+        // A bundled theme extends the bundled parent rather than a user file of that name,
+        // and a user file may extend the bundled theme it replaces
+        let bundled = !path.is_file() || chain.last() == Some(&parent);
+        let mut base = match builtin(&parent) {
+            Some(content) if bundled => {
+                resolve(Path::new(&format!("<built-in {}>", parent)), content, chain, dirs, layers)?
+            }
+            _ => {
+                if chain.contains(&parent) {
+                    chain.push(parent);
+                    anyhow::bail!("Themes extend each other: {}", chain.join(" -> "));
+                }
+                chain.push(parent.clone());
+                let base = parent_table(&parent, chain, dirs, layers)?;
+                chain.pop();
+                base
+            }
+        };
+
+        // The child's own name wins even when it does not set one
+        base.remove("name");
+        merge_tables(&mut base, table);
+        table = base;
+    }
+
+    if !table.contains_key("name") {
+        if let Some(name) = chain.first() {
+            table.insert("name".to_string(), toml::Value::String(name.clone()));
+        }
+    }
+
+    Ok(table)
+}
+
+// Font paths are relative to the file that names them, not to whichever theme extends it
+fn anchor_font_files(path: &Path, table: &mut toml::Table) {
+    let Some(dir) = path.parent().filter(|_| path.is_file()) else {
+        return;
+    };
+    if let Some(toml::Value::Array(files)) = table.get_mut("font_files") {
+        for file in files.iter_mut() {
+            if let toml::Value::String(s) = file {
+                // Joining an absolute path replaces `dir` entirely
+                *s = dir.join(expand_home(Path::new(s.as_str()))).to_string_lossy().into_owned();
+            }
+        }
+    }
+}
+
+fn parent_table(
+    name: &str,
+    chain: &mut Vec<String>,
+    dirs: &[PathBuf],
+    layers: &mut Vec<(PathBuf, String)>,
+) -> Result<toml::Table> {
+    match Theme::locate(name, dirs) {
+        Some(Source::File(path)) => {
+            let content = std::fs::read_to_string(&path)?;
+            resolve(&path, &content, chain, dirs, layers)
+        }
+        Some(Source::Builtin(content)) => {
+            resolve(Path::new(&format!("<built-in {}>", name)), content, chain, dirs, layers)
+        }
+        Some(Source::Pywal(path)) => {
+            let content = import::import(import::Format::Pywal, &path, Some(name))?;
+            resolve(&path, &content, chain, dirs, layers)
+        }
+        None => anyhow::bail!("Parent theme {} not found", name),
+    }
+}
+
+fn substitute_variables(table: &mut toml::Table) -> Result<()> {
+    let variables: BTreeMap<String, String> = match table.get("palette") {
+        Some(palette) => palette
+            .clone()
+            .try_into()
+            .map_err(|_| anyhow::anyhow!("[palette] entries must be strings"))?,
+        None => return Ok(()),
+    };
+
+    for (key, value) in table.iter_mut() {
+        if key != "palette" {
+            substitute(key, value, &variables)?;
+        }
+    }
+    Ok(())
+}
+
+fn substitute(key: &str, value: &mut toml::Value, variables: &BTreeMap<String, String>) -> Result<()> {
+    match value {
+        toml::Value::String(text) if text.starts_with('$') => {
+            *text = lookup_variable(key, text, variables, &mut Vec::new())?;
+        }
+        toml::Value::Table(inner) => {
+            for (sub, value) in inner.iter_mut() {
+                substitute(&format!("{}.{}", key, sub), value, variables)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+// Variables may refer to other variables, e.g. `selection = "$accent"`
+fn lookup_variable(key: &str, reference: &str, variables: &BTreeMap<String, String>, seen: &mut Vec<String>) -> Result<String> {
+    let name = &reference[1..];
+    if seen.iter().any(|s| s == name) {
+        seen.push(name.to_string());
+        anyhow::bail!("Palette variables refer to each other: ${}", seen.join(" -> $"));
+    }
+    let value = variables
+        .get(name)
+        .ok_or_else(|| anyhow::anyhow!("Unknown palette variable `{}` in `{}`", reference, key))?;
+
+    if value.starts_with('$') {
+        seen.push(name.to_string());
+        lookup_variable(key, value, variables, seen)
+    } else {
+        Ok(value.clone())
+    }
+}
+
+// Line and column of `key = ...` when the dotted key is spelled out in this file, either
+// under its `[table]` header or as a dotted key
+fn find_key(content: &str, key: &str) -> Option<(usize, usize)> {
+    let mut table = String::new();
+    for (index, line) in content.lines().enumerate() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with('#') {
+            continue;
+        }
+        if trimmed.starts_with('[') {
+            let header = trimmed.trim_start_matches('[').split(']').next().unwrap_or_default();
+            table = dotted(header);
+            continue;
+        }
+        let Some((name, _)) = trimmed.split_once('=') else {
+            continue;
+        };
+        let name = dotted(name);
+        let full = if table.is_empty() { name } else { format!("{}.{}", table, name) };
+        if full == key {
+            return Some((index + 1, line.len() - trimmed.len() + 1));
+        }
+    }
+    None
+}
+
+// `a . "b"` -> `a.b`
+fn dotted(key: &str) -> String {
+    key.split('.').map(|part| part.trim().trim_matches('"')).collect::<Vec<_>>().join(".")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::scratch::ScratchDir;
+
+    #[test]
+    fn bundled_themes_ignore_a_user_default() {
+        let dir = ScratchDir::new("themes-user-default");
+        std::fs::write(
+            dir.join("default.toml"),
+            "name = \"default\"\nbackground_color = \"#2E344000\"\ntext_color = \"#ECEFF4FF\"\n\
+             selected_background_color = \"#000000\"\nselected_text_color = \"#ffffff\"\n\
+             border_color = \"#808080\"\nborder_width = 1.0\nborder_radius = 4.0\npadding = 10.0\n",
+        )
+        .unwrap();
+
+        let Some(Source::Builtin(content)) = Theme::locate("ocean", &[dir.to_path_buf()]) else {
+            panic!("ocean should be bundled");
+        };
+        let theme = Theme::parse(Path::new("<built-in ocean>"), content, "ocean", &[dir.to_path_buf()]).unwrap();
+
+        assert_eq!(theme.background_color.to_string(), "#0F3460");
+        assert_eq!(theme.text_color.to_string(), "#16537E");
+        assert_eq!(theme.border_width, 2.0);
+    }
+
+    fn load_error(dir: &Path, file: &str) -> String {
+        Theme::load_file(&dir.join(file), &[]).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn reports_nested_keys_at_their_table() {
+        let dir = ScratchDir::new("themes-nested-key");
+        std::fs::write(
+            dir.join("t.toml"),
+            "extends = \"default\"\n\ntext_color = \"#ffffff\"\n\n[selected_row]\ntext_color = \"#nope\"\n",
+        )
+        .unwrap();
+        let error = load_error(&dir, "t.toml");
+
+        assert!(error.contains("t.toml:6:1: invalid value for `selected_row.text_color`"), "{}", error);
+    }
+
+    #[test]
+    fn reports_parent_keys_against_the_parent() {
+        let dir = ScratchDir::new("themes-parent-key");
+        std::fs::write(dir.join("child.toml"), "extends = \"parent\"\nborder_radius = 2.0\n").unwrap();
+        std::fs::write(dir.join("parent.toml"), "extends = \"default\"\n\nborder_width = \"wide\"\n").unwrap();
+        let error = load_error(&dir, "child.toml");
+
+        assert!(error.contains("parent.toml:3:1: invalid value for `border_width`"), "{}", error);
+    }
+}
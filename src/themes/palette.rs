@@ -0,0 +1,123 @@
+use iced::{Color, Font};
+
+use super::{ElementStyle, Theme, ThemeColor};
+
+// Fully resolved styling for one part of the window
+#[derive(Debug, Clone, Copy)]
+pub struct ElementPalette {
+    pub background: Color,
+    pub text: Color,
+    pub border: Color,
+    pub border_width: f32,
+    pub border_radius: f32,
+    pub padding: f32,
+    pub spacing: f32,
+    pub font: Font,
+    // None keeps the size from `font_size` in the config
+    pub text_size: Option<f32>,
+}
+
+impl ElementPalette {
+    fn apply(self, style: &ElementStyle) -> Self {
+        let color = |c: &Option<ThemeColor>, base: Color| c.as_ref().map_or(base, ThemeColor::color);
+        Self {
+            background: color(&style.background, self.background),
+            text: color(&style.text_color, self.text),
+            border: color(&style.border_color, self.border),
+            border_width: style.border_width.unwrap_or(self.border_width),
+            border_radius: style.border_radius.unwrap_or(self.border_radius),
+            padding: style.padding.unwrap_or(self.padding),
+            spacing: style.spacing.unwrap_or(self.spacing),
+            font: style.font.apply(self.font),
+            text_size: style.font.size.or(self.text_size),
+        }
+    }
+
+    fn plain(self) -> Self {
+        Self {
+            background: Color::TRANSPARENT,
+            border: Color::TRANSPARENT,
+            border_width: 0.0,
+            border_radius: 0.0,
+            ..self
+        }
+    }
+}
+
+// Everything `view` needs, resolved once when a theme is loaded
+#[derive(Debug, Clone, Copy)]
+pub struct Palette {
+    pub window: ElementPalette,
+    pub input: ElementPalette,
+    pub prompt: ElementPalette,
+    pub list: ElementPalette,
+    pub row: ElementPalette,
+    pub selected_row: ElementPalette,
+    pub alternate_row: ElementPalette,
+    pub status_bar: ElementPalette,
+}
+
+impl From<&Theme> for Palette {
+    // Sections only override what they set; the rest comes from the flat fields so
+    // themes written before sections existed look the same
+    fn from(theme: &Theme) -> Self {
+        let accent = theme.selected_background_color.color();
+
+        let window = ElementPalette {
+            background: theme.background_color.color(),
+            text: theme.text_color.color(),
+            border: theme.border_color.color(),
+            border_width: theme.border_width,
+            border_radius: 0.0,
+            padding: 16.0,
+            spacing: 8.0,
+            font: Font::MONOSPACE,
+            text_size: None,
+        }
+        .apply(&theme.window);
+
+        // Children inherit the window's font and colors
+        let inherited = ElementPalette {
+            border_radius: theme.border_radius,
+            spacing: 0.0,
+            ..window
+        };
+
+        // The flat `padding` is the search field's; sections can override it
+        let input = ElementPalette { padding: theme.padding, ..inherited }.apply(&theme.input);
+        let prompt = ElementPalette {
+            text: accent,
+            padding: input.padding,
+            spacing: 8.0,
+            ..inherited.plain()
+        }
+        .apply(&theme.prompt);
+        let list = ElementPalette { padding: 2.0, spacing: 2.0, ..inherited.plain() }.apply(&theme.list);
+        let row = ElementPalette {
+            padding: 8.0,
+            border_width: 0.0,
+            border_radius: 0.0,
+            ..inherited
+        }
+        .apply(&theme.row);
+        let selected_row = ElementPalette {
+            background: accent,
+            text: theme.selected_text_color.color(),
+            ..row
+        }
+        .apply(&theme.selected_row);
+        let alternate_row = row.apply(&theme.alternate_row);
+        let status_bar = ElementPalette { text: accent, padding: 0.0, ..inherited.plain() }.apply(&theme.status_bar);
+
+        Self {
+            window,
+            input,
+            prompt,
+            list,
+            row,
+            selected_row,
+            alternate_row,
+            status_bar,
+        }
+    }
+}
@@ -1,151 +1,301 @@
 use std::os::unix::fs::PermissionsExt;
 use iced::{
-    widget::{text_input, container, Column, Text, text_input::TextInput},
-    executor, Application, Command, Element, Theme, Length, Subscription,
+    widget::{container, image, mouse_area, svg, Column, Container, Row, Space, Text, text_input::TextInput},
+    executor, font, futures::future::{abortable, AbortHandle}, Alignment, Application, Command, Element, Theme, Length, Subscription,
     theme::{self, Text as TextTheme},
-    Background, Color,
     keyboard,
     mouse,
     event::Event,
     subscription,
+    window,
 };
-use crate::{config::Config, themes::{Theme as AppTheme}};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+use crate::{config::{self, Config, Layout, PreviewPosition, Source}, icons::{Icon, Icons}, ipc::{self, Request}, placement::Placement, preview::{self, Content, Target}, themes::{ElementPalette, Palette, Theme as AppTheme}, watcher::{self, RecursiveMode}};
 
-// Custom styles for our UI elements
-mod style {
-    use super::*;
+mod settings;
+mod style;
+mod theme_editor;
 
-    #[derive(Debug, Clone, Copy)]
-    pub struct DarkContainer {
-        pub bg_color: Color,
-        pub text_color: Color,
-        pub border_color: Option<Color>,
-        pub border_width: f32,
-        pub border_radius: f32,
-    }
+use fuzzy_matcher::FuzzyMatcher;
+use fuzzy_matcher::skim::SkimMatcherV2;
+use std::process::Command as ProcessCommand;
+use anyhow::Result;
+use theme_editor::{Editing, ThemeEditor};
 
-    impl container::StyleSheet for DarkContainer {
-        type Style = Theme;
+// iced's default line height, relative to the text size
+const LINE_HEIGHT: f32 = 1.3;
+// Average advance of a character relative to the text size; exact for the default monospace font
+const CHAR_WIDTH: f32 = 0.6;
+// Part of the bar taken by the input, and its least width
+const BAR_INPUT_SHARE: f32 = 0.25;
+const BAR_INPUT_MIN_WIDTH: f32 = 160.0;
+// Space between a grid tile's icon and its label
+const TILE_GAP: f32 = 6.0;
+// Part of the window the results keep beside the preview pane, matching their FillPortion(3) to its 2
+const RESULTS_SHARE: f32 = 0.6;
+// Height of the bottom preview pane when the window is sized to the results
+const PANE_LINES: f32 = 10.0;
 
-        fn appearance(&self, _style: &Self::Style) -> container::Appearance {
-            container::Appearance {
-                text_color: Some(self.text_color),
-                background: Some(Background::Color(self.bg_color)),
-                border_radius: self.border_radius.into(),
-                border_width: self.border_width,
-                border_color: self.border_color.unwrap_or(self.bg_color),
-            }
-        }
-    }
+const BACK_TO_MAIN: &str = "Back to Main";
+const BACK_TO_SETTINGS: &str = "Back to Settings";
+const EDIT_THEME: &str = "Edit Theme";
+const OTHER_TERMINAL: &str = "Other…";
+const ADD_PATH: &str = "Add Path…";
+const REMOVE_PATH: &str = "Remove ";
 
-    #[derive(Debug, Clone, Copy)]
-    pub struct DarkTextInput {
-        pub bg_color: Color,
-        pub text_color: Color,
-        pub border_color: Color,
-        pub border_radius: f32,
-        pub border_width: f32,
-    }
+#[derive(Debug, Clone)]
+pub enum Message {
+    InputChanged(String),
+    Execute(String),
+    KeyPressed(iced::keyboard::KeyCode, keyboard::Modifiers),
+    Error(String),
+    EntrySelected(usize),
+    WheelScrolled(mouse::ScrollDelta),
+    Ipc(Request, Option<ipc::Reply>),
+    // The selection reached the client that pushed the entries
+    Answered,
+    // The client that asked to quit has its answer
+    Quit,
+    ConfigChanged,
+    ThemeChanged,
+    FontLoaded(Result<(), font::Error>),
+    PreviewLoaded(u64, Option<Content>),
+    // Icons looked up for the given icon size
+    IconsLoaded(u16, Vec<(String, Option<Icon>)>),
+    // Where the window goes, and whether it is being shown and should take focus
+    Placed(Option<Placement>, bool),
+}
 
-    impl text_input::StyleSheet for DarkTextInput {
-        type Style = Theme;
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum Mode {
+    Apps,
+    Settings,
+    Dmenu,
+}
 
-        fn active(&self, _style: &Self::Style) -> text_input::Appearance {
-            text_input::Appearance {
-                background: Background::Color(self.bg_color),
-                border_radius: self.border_radius.into(),
-                border_width: self.border_width,
-                border_color: self.border_color,
-                icon_color: self.text_color,
-            }
-        }
+#[derive(Debug, Default)]
+pub struct Flags {
+    pub daemon: bool,
+    pub config: Config,
+    // Set by --theme
+    pub theme_override: Option<String>,
+    pub theme: AppTheme,
+    // Why the theme could not be loaded, shown once the window is up
+    pub status: Option<String>,
+    // Config files and variables left out at startup
+    pub config_errors: Vec<String>,
+    pub placement: Placement,
+    pub texts: Texts,
+}
 
-        fn focused(&self, style: &Self::Style) -> text_input::Appearance {
-            let active = self.active(style);
-            text_input::Appearance {
-                border_color: self.text_color,
-                ..active
-            }
-        }
+// Prompts, placeholder and message set by command line flags or IPC; each wins over its config value
+#[derive(Debug, Default, Clone)]
+pub struct Texts {
+    pub prompts: HashMap<Mode, String>,
+    pub placeholder: Option<String>,
+    pub message: Option<String>,
+}
 
-        fn placeholder_color(&self, _style: &Self::Style) -> Color {
-            Color {
-                a: 0.5,
-                ..self.text_color
+impl Texts {
+    // Without a mode the prompt is for apps and dmenu; settings keeps its own
+    pub fn set_prompt(&mut self, mode: Option<Mode>, prompt: String) {
+        match mode {
+            Some(mode) => {
+                self.prompts.insert(mode, prompt);
+            }
+            None => {
+                self.prompts.insert(Mode::Apps, prompt.clone());
+                self.prompts.insert(Mode::Dmenu, prompt);
             }
         }
+    }
 
-        fn value_color(&self, _style: &Self::Style) -> Color {
-            self.text_color
+    // The requests that give a running instance the same texts
+    pub fn requests(&self) -> Vec<Request> {
+        let mut requests: Vec<Request> = self
+            .prompts
+            .iter()
+            .map(|(mode, prompt)| Request::SetPrompt { prompt: prompt.clone(), mode: Some(*mode) })
+            .collect();
+        if let Some(placeholder) = &self.placeholder {
+            requests.push(Request::SetPlaceholder { placeholder: placeholder.clone() });
         }
-
-        fn selection_color(&self, _style: &Self::Style) -> Color {
-            Color { a: 0.2, ..self.text_color }
+        if let Some(message) = &self.message {
+            requests.push(Request::SetMessage { message: message.clone() });
         }
+        requests
+    }
+}
 
-        fn disabled(&self, style: &Self::Style) -> text_input::Appearance {
-            self.active(style)
+// What besides the input and the results takes up the window's height
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Extras {
+    pub status: bool,
+    // A preview pane below the results
+    pub pane: bool,
+    pub message_lines: usize,
+}
+
+// A settings value being typed into the search field
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SettingInput {
+    Value(&'static str),
+    AddPath(&'static str),
+    Terminal,
+}
+
+// One level of the settings menu. Each screen is opened from an entry of the one below it,
+// and going back pops it off the stack
+enum Screen {
+    Settings,
+    // The terminals and choices are worked out once, as they come from $PATH and xrandr
+    Terminals(Vec<String>),
+    Choices(&'static str, Vec<String>),
+    Paths(&'static str),
+    Editor(Box<ThemeEditor>),
+    // Keeps the list of the screen below while the value is typed in
+    Input(SettingInput),
+}
+
+impl Screen {
+    fn title(&self) -> String {
+        match self {
+            Screen::Settings => "Settings".to_string(),
+            Screen::Terminals(_) | Screen::Input(SettingInput::Terminal) => "terminal".to_string(),
+            Screen::Choices(key, _) | Screen::Paths(key) | Screen::Input(SettingInput::Value(key)) => key.to_string(),
+            Screen::Input(SettingInput::AddPath(_)) => "Add Path".to_string(),
+            Screen::Editor(editor) => match &editor.editing {
+                Some(Editing::Field { key, .. }) => format!("Theme Editor › {}", key),
+                Some(Editing::SaveName) => "Theme Editor › Save as".to_string(),
+                None => "Theme Editor".to_string(),
+            },
         }
+    }
 
-        fn disabled_color(&self, _style: &Self::Style) -> Color {
-            Color { a: 0.5, ..self.text_color }
+    // The entry, or config field, of the screen below that opens this one
+    fn origin(&self) -> &'static str {
+        match self {
+            Screen::Settings => "",
+            Screen::Terminals(_) => "terminal",
+            Screen::Choices(key, _) | Screen::Paths(key) | Screen::Input(SettingInput::Value(key)) => key,
+            Screen::Editor(_) => EDIT_THEME,
+            Screen::Input(SettingInput::AddPath(_)) => ADD_PATH,
+            Screen::Input(SettingInput::Terminal) => OTHER_TERMINAL,
         }
     }
 }
-use fuzzy_matcher::FuzzyMatcher;
-use fuzzy_matcher::skim::SkimMatcherV2;
-use std::process::Command as ProcessCommand;
-use anyhow::Result;
 
-#[derive(Debug, Clone)]
-pub enum Message {
-    InputChanged(String),
-    Execute(String),
-    KeyPressed(iced::keyboard::KeyCode),
-    Error(String),
-    EntrySelected(usize),
-    WheelScrolled(mouse::ScrollDelta),
-    ChangeTheme(String),
-    BackToMain,
+struct Preview {
+    name: String,
+    // The committed theme and the status line from before the preview, restored when the
+    // preview ends without Enter
+    previous: AppTheme,
+    status: Option<String>,
+}
+
+#[derive(Default)]
+struct PreviewPane {
+    // The selected entry the pane was loaded for
+    entry: Option<String>,
+    content: Option<Content>,
+    // Counts loads, so a result arriving after the selection moved on is dropped
+    generation: u64,
+    task: Option<AbortHandle>,
 }
 
 pub struct MenuWindow {
     config: Config,
     theme: AppTheme,
+    palette: Palette,
     input_value: String,
     entries: Vec<String>,
+    dmenu_entries: Vec<String>,
+    // Waiting for the selection from entries pushed over IPC
+    selection_reply: Option<ipc::Reply>,
     filtered_entries: Vec<String>,
     selected_index: usize,
     display_start_index: usize,
-    settings_mode: bool,
+    mode: Mode,
+    daemon: bool,
+    visible: bool,
+    status: Option<String>,
+    // Set by --theme; wins over `config.theme` until a theme is picked in settings
+    theme_override: Option<String>,
+    // Set while the settings list shows a theme other than the configured one
+    preview: Option<Preview>,
+    // Settings screens, innermost last; empty outside settings
+    screens: Vec<Screen>,
+    // Where leaving settings goes back to
+    previous_mode: Mode,
+    // Set when the input's own Backspace emptied it, so that press does not also go back
+    input_just_cleared: bool,
+    // Font files already handed to the renderer; they stay registered for the whole run
+    loaded_fonts: HashSet<PathBuf>,
+    placement: Placement,
+    icons: Icons,
+    pane: PreviewPane,
+    // Where each source's results start in `filtered_entries`, in apps mode
+    sections: Vec<(usize, Source)>,
+    texts: Texts,
+    // Config files and variables the current configuration was loaded without
+    config_errors: Vec<String>,
+    // Theme names found on the search path, listed again when the config loads, settings
+    // opens or a theme is saved rather than on every keystroke
+    themes: Vec<String>,
 }
 
 impl Application for MenuWindow {
     type Message = Message;
     type Theme = Theme;
     type Executor = executor::Default;
-    type Flags = ();
+    type Flags = Flags;
 
-    fn new(_flags: ()) -> (Self, Command<Message>) {
-        let config = Config::load().unwrap_or_default();
-        let theme = AppTheme::load(&config.theme).unwrap_or_default();
-        
-        let entries = Self::load_applications().unwrap_or_default();
-        let filtered_entries = entries.clone().into_iter().take(config.max_entries).collect();
-        
-        (
-            Self {
-                config,
-                theme,
-                input_value: String::new(),
-                entries,
-                filtered_entries,
-                selected_index: 0,
-                display_start_index: 0,
-                settings_mode: false,
-            },
-            Command::none(),
-        )
+    fn new(flags: Flags) -> (Self, Command<Message>) {
+        let config = flags.config;
+        let config_icon_size = config.icon_size;
+        let entries = Self::load_applications(&config.search_paths).unwrap_or_default();
+        let themes = AppTheme::get_available_themes(&config.theme_paths);
+
+        let mut menu = Self {
+            config,
+            palette: Palette::from(&flags.theme),
+            theme: flags.theme,
+            input_value: String::new(),
+            entries,
+            dmenu_entries: Vec::new(),
+            selection_reply: None,
+            filtered_entries: Vec::new(),
+            selected_index: 0,
+            display_start_index: 0,
+            mode: Mode::Apps,
+            daemon: flags.daemon,
+            visible: !flags.daemon,
+            status: flags.status,
+            theme_override: flags.theme_override,
+            preview: None,
+            screens: Vec::new(),
+            previous_mode: Mode::Apps,
+            input_just_cleared: false,
+            loaded_fonts: HashSet::new(),
+            placement: flags.placement,
+            icons: Icons::new(config_icon_size),
+            pane: PreviewPane::default(),
+            sections: Vec::new(),
+            texts: flags.texts,
+            config_errors: flags.config_errors,
+            themes,
+        };
+        menu.filter_entries();
+        let mut commands = vec![menu.load_fonts(), menu.fit_height(), menu.update_pane(), menu.load_icons()];
+        // Window settings have no fullscreen flag; the window opens sized to the monitor first
+        if menu.placement.fullscreen && menu.visible {
+            commands.push(window::change_mode(window::Mode::Fullscreen));
+        }
+        (menu, Command::batch(commands))
     }
 
     fn title(&self) -> String {
@@ -153,38 +303,57 @@ impl Application for MenuWindow {
     }
 
     fn update(&mut self, message: Message) -> Command<Message> {
-        match message {
+        let command = match message {
             Message::InputChanged(value) => {
-                self.input_value = value.clone();
-                
-                // Check for settings command
-                if value.trim() == "> Settings" || value.trim() == ">Settings" {
-                    self.settings_mode = true;
-                    self.filtered_entries = self.get_settings_options();
-                    self.selected_index = 0;
-                    self.display_start_index = 0;
+                self.input_just_cleared = !self.input_value.is_empty() && value.is_empty();
+                if matches!(self.screens.last(), Some(Screen::Input(_))) {
+                    self.input_value = value;
+                    Command::none()
+                } else if self.editor().is_some_and(|e| e.editing.is_some()) {
+                    self.editor_input(value)
                 } else {
-                    self.settings_mode = false;
-                    self.filter_entries();
-                    self.selected_index = 0;
-                    self.display_start_index = 0;
+                    self.set_query(value);
+                    Command::none()
                 }
-                Command::none()
             }
             Message::Execute(cmd) => {
-                // Don't execute math results as commands
-                if cmd.starts_with("Answer: ") {
-                    Command::none()
-                } else {
-                    if let Err(e) = self.execute_command(&cmd) {
-                        Command::perform(async move { e.to_string() }, Message::Error)
-                    } else {
-                        std::process::exit(0);
+                if self.mode == Mode::Dmenu {
+                    match self.selection_reply.take() {
+                        Some(reply) => Command::perform(reply.send(cmd), |()| Message::Answered),
+                        // Nobody reads a daemon's stdout, e.g. after `msg set-mode dmenu`
+                        None if self.daemon => {
+                            log::info!("Selected {} with no client waiting for it", cmd);
+                            self.dismiss()
+                        }
+                        None => {
+                            println!("{}", cmd);
+                            self.dismiss()
+                        }
                     }
+                } else if let Err(e) = self.execute_command(&cmd) {
+                    Command::perform(async move { e.to_string() }, Message::Error)
+                } else {
+                    self.dismiss()
                 }
             }
-            Message::KeyPressed(key_code) => {
+            Message::KeyPressed(key_code, modifiers) => {
                 match key_code {
+                    keyboard::KeyCode::Comma if modifiers.command() => {
+                        if self.mode != Mode::Settings {
+                            self.set_mode(Mode::Settings);
+                        }
+                        Command::none()
+                    }
+                    // The list stays put while a value is being typed in
+                    keyboard::KeyCode::Up | keyboard::KeyCode::Down if self.typing_value() => Command::none(),
+                    keyboard::KeyCode::Up if self.layout() == Layout::Grid => {
+                        self.move_row(-1);
+                        Command::none()
+                    }
+                    keyboard::KeyCode::Down if self.layout() == Layout::Grid => {
+                        self.move_row(1);
+                        Command::none()
+                    }
                     keyboard::KeyCode::Up => {
                         self.move_selection(-1);
                         Command::none()
@@ -193,324 +362,1724 @@ impl Application for MenuWindow {
                         self.move_selection(1);
                         Command::none()
                     }
-                    keyboard::KeyCode::Enter => {
-                        if self.selected_index < self.filtered_entries.len() {
-                            if let Some(entry) = self.filtered_entries.get(self.selected_index).cloned() {
-                                if self.settings_mode {
-                                    self.handle_settings_selection(&entry)
-                                } else {
-                                    Command::perform(async { entry }, Message::Execute)
-                                }
-                            } else {
-                                Command::none()
-                            }
-                        } else {
-                            Command::none()
-                        }
+                    // The bar and the grid lay results out left to right, so the side arrows move through them
+                    keyboard::KeyCode::Left if self.layout() != Layout::List && !self.typing_value() => {
+                        self.move_selection(-1);
+                        Command::none()
+                    }
+                    keyboard::KeyCode::Right if self.layout() != Layout::List && !self.typing_value() => {
+                        self.move_selection(1);
+                        Command::none()
                     }
-                    keyboard::KeyCode::Escape => {
-                        std::process::exit(0);
+                    keyboard::KeyCode::Enter if matches!(self.screens.last(), Some(Screen::Input(_))) => {
+                        self.submit_setting()
                     }
-                    _ => Command::none(),
-                }
-            }
-            Message::EntrySelected(index) => {
-                if index < self.filtered_entries.len() {
-                    self.selected_index = index;
-                    if let Some(entry) = self.filtered_entries.get(index).cloned() {
-                        if self.settings_mode {
-                            self.handle_settings_selection(&entry)
+                    keyboard::KeyCode::Enter if self.editor().is_some_and(|e| e.editing.is_some()) => {
+                        self.editor_submit()
+                    }
+                    keyboard::KeyCode::Enter => self.activate(self.selected_index),
+                    keyboard::KeyCode::Tab if !self.sections.is_empty() => {
+                        self.jump_section(!modifiers.shift());
+                        Command::none()
+                    }
+                    keyboard::KeyCode::Escape if self.mode == Mode::Settings => self.back(),
+                    keyboard::KeyCode::Escape => self.dismiss(),
+                    keyboard::KeyCode::Backspace => {
+                        let cleared = std::mem::take(&mut self.input_just_cleared);
+                        if self.mode == Mode::Settings && self.input_value.is_empty() && !cleared && !self.typing_value() {
+                            self.back()
                         } else {
-                            Command::perform(async { entry }, Message::Execute)
+                            Command::none()
                         }
-                    } else {
-                        Command::none()
                     }
-                } else {
-                    Command::none()
+                    _ => Command::none(),
                 }
             }
+            Message::EntrySelected(index) => self.activate(index),
             Message::WheelScrolled(delta) => {
                 match delta {
                     mouse::ScrollDelta::Lines { y, .. } |
                     mouse::ScrollDelta::Pixels { y, .. } => {
-                        if y > 0.0 && self.display_start_index > 0 {
-                            self.display_start_index = self.display_start_index.saturating_sub(1);
-                        } else if y < 0.0 && self.display_start_index < self.filtered_entries.len().saturating_sub(self.config.max_entries) {
-                            self.display_start_index += 1;
+                        let (visible, step) = self.visible_entries();
+                        if self.layout() == Layout::Bar {
+                            if y != 0.0 {
+                                self.move_selection(if y > 0.0 { -1 } else { 1 });
+                            }
+                        } else if y > 0.0 && self.display_start_index > 0 {
+                            self.display_start_index = self.display_start_index.saturating_sub(step);
+                        } else if y < 0.0 && self.display_start_index + visible < self.filtered_entries.len() {
+                            self.display_start_index += step;
                         }
                     }
                 }
                 Command::none()
             }
-            Message::Error(_) => Command::none(),
-            Message::ChangeTheme(theme_name) => {
-                if let Ok(new_theme) = AppTheme::load(&theme_name) {
-                    self.theme = new_theme;
-                    self.config.theme = theme_name;
-                    let _ = self.config.save();
+            Message::Error(e) => {
+                log::error!("{}", e);
+                Command::none()
+            }
+            Message::Ipc(request, reply) => self.handle_request(request, reply),
+            Message::Answered => self.dismiss(),
+            Message::Quit => std::process::exit(0),
+            Message::ConfigChanged => self.reload_config(),
+            Message::ThemeChanged => self.reload_theme(),
+            Message::FontLoaded(result) => {
+                if result.is_err() {
+                    log::error!("Failed to load a theme font");
                 }
                 Command::none()
             }
-            Message::BackToMain => {
-                self.settings_mode = false;
-                self.input_value.clear();
-                self.filtered_entries = self.entries.clone().into_iter().take(self.config.max_entries).collect();
-                self.selected_index = 0;
-                self.display_start_index = 0;
+            Message::PreviewLoaded(generation, content) => {
+                if generation == self.pane.generation {
+                    self.pane.task = None;
+                    self.pane.content = content;
+                }
                 Command::none()
             }
-        }
-    }
-
-    fn view(&self) -> Element<Message> {
-        let input = TextInput::new(
-            "Type to search...",
-            &self.input_value,
-        )
-        .on_input(Message::InputChanged)
-        .padding(12)
-        .style(theme::TextInput::Custom(Box::new(style::DarkTextInput {
-            bg_color: self.theme.parse_color(&self.theme.background_color),
-            text_color: self.theme.parse_color(&self.theme.text_color),
-            border_color: self.theme.parse_color(&self.theme.border_color),
-            border_radius: self.theme.border_radius,
-            border_width: self.theme.border_width,
-        })));
-
-        // Always create a fixed number of entries (max_entries)
-        let visible_entries = (0..self.config.max_entries)
-            .map(|i| {
-                let actual_index = i + self.display_start_index;
-                let entry_text = self.filtered_entries
-                    .get(actual_index)
-                    .map(|s| s.as_str())
-                    .unwrap_or("");
-
-                // Add prefix for settings mode
-                let display_text = if self.settings_mode && !entry_text.is_empty() {
-                    if entry_text.starts_with("Theme: ") {
-                        entry_text.to_string()
-                    } else {
-                        format!("⚙ {}", entry_text)
-                    }
-                } else {
-                    entry_text.to_string()
-                };
-
-                let (bg_color, text_color) = if !entry_text.is_empty() && actual_index == self.selected_index {
-                    (
-                        self.theme.parse_color(&self.theme.selected_background_color),
-                        self.theme.parse_color(&self.theme.selected_text_color),
-                    )
+            Message::IconsLoaded(size, loaded) => {
+                if size == self.icons.size() {
+                    self.icons.insert(loaded);
+                }
+                Command::none()
+            }
+            Message::Placed(placement, showing) => {
+                // A failed lookup still shows the window where it was
+                let moved = self.move_window(placement.unwrap_or(self.placement));
+                if showing && self.visible {
+                    Command::batch([moved, window::gain_focus()])
                 } else {
-                    (
-                        self.theme.parse_color(&self.theme.background_color),
-                        self.theme.parse_color(&self.theme.text_color),
-                    )
-                };
-
-                let text = Text::new(display_text)
-                    .style(TextTheme::Color(text_color));
-
-                container(text)
-                    .width(Length::Fill)
-                    .padding(8)
-                    .style(theme::Container::Custom(Box::new(style::DarkContainer {
-                        bg_color,
-                        text_color,
-                        border_color: None,
-                        border_width: 0.0,
-                        border_radius: 0.0,
-                    })))
-                    .into()
-            })
-            .collect();
-
-        let entries: Element<_> = Column::with_children(visible_entries)
-            .spacing(2)
-            .padding(2)
-            .into();
-
-        let col = Column::new()
-            .push(input)
-            .push(entries)
-            .max_width(self.config.width as f32)
-            .spacing(8);
+                    moved
+                }
+            }
+        };
+        Command::batch([command, self.preview_selected(), self.fit_height(), self.update_pane(), self.load_icons()])
+    }
 
-        container(col)
-            .padding(16)
-            .style(theme::Container::Custom(Box::new(style::DarkContainer {
-                bg_color: self.theme.parse_color(&self.theme.background_color),
-                text_color: self.theme.parse_color(&self.theme.text_color),
-                border_color: Some(self.theme.parse_color(&self.theme.border_color)),
-                border_width: self.theme.border_width,
-                border_radius: 0.0,
-            })))
-            .into()
+    fn view(&self) -> Element<'_, Message> {
+        match self.layout() {
+            Layout::List => self.list_view(),
+            Layout::Bar => self.bar_view(),
+            Layout::Grid => self.grid_view(),
+        }
     }
 
     fn subscription(&self) -> Subscription<Message> {
-        subscription::events_with(|event, _status| {
+        let events = subscription::events_with(|event, _status| {
             match event {
-                Event::Keyboard(keyboard::Event::KeyPressed { key_code, .. }) => {
-                    Some(Message::KeyPressed(key_code))
+                Event::Keyboard(keyboard::Event::KeyPressed { key_code, modifiers }) => {
+                    Some(Message::KeyPressed(key_code, modifiers))
                 }
                 Event::Mouse(mouse::Event::WheelScrolled { delta }) => {
                     Some(Message::WheelScrolled(delta))
                 }
                 _ => None,
             }
-        })
+        });
+
+        let mut subscriptions = vec![
+            events,
+            ipc::listen().map(|(request, reply)| Message::Ipc(request, reply)),
+            watcher::watch(Config::dir(), "toml", RecursiveMode::Recursive).map(|_| Message::ConfigChanged),
+        ];
+        // Theme files elsewhere, such as those from `theme_paths`, `--theme ./mine.toml` or
+        // pywal's colors.json, are watched where they are
+        let mut watched: Vec<(&Path, &OsStr)> = Vec::new();
+        for file in &self.theme.files {
+            let (Some(dir), Some(extension)) = (file.parent(), file.extension()) else {
+                continue;
+            };
+            if !dir.starts_with(Config::dir()) && !watched.contains(&(dir, extension)) {
+                watched.push((dir, extension));
+            }
+        }
+        for (dir, extension) in watched {
+            let extension = extension.to_string_lossy().into_owned();
+            let watch = watcher::watch(dir.to_path_buf(), extension, RecursiveMode::NonRecursive);
+            subscriptions.push(watch.map(|_| Message::ThemeChanged));
+        }
+        Subscription::batch(subscriptions)
     }
 }
 
 impl MenuWindow {
-    fn load_applications() -> Result<Vec<String>> {
-        let mut entries = Vec::new();
-        for path in &Config::default().search_paths {
-            if let Ok(entries_in_path) = std::fs::read_dir(path) {
-                for entry in entries_in_path.filter_map(Result::ok) {
-                    if let Ok(file_type) = entry.file_type() {
-                        if file_type.is_file() {
-                            if let Ok(metadata) = entry.metadata() {
-                                if metadata.permissions().mode() & 0o111 != 0 {
-                                    if let Some(name) = entry.file_name().to_str() {
-                                        entries.push(name.to_string());
-                                    }
-                                }
-                            }
-                        }
-                    }
+    fn list_view(&self) -> Element<'_, Message> {
+        let palette = &self.palette;
+
+        // Always create a fixed number of lines, padding with empty rows; section headers
+        // take a line each
+        let rows = self.list_rows();
+        let mut visible_entries = Vec::with_capacity(rows);
+        let mut actual_index = self.display_start_index;
+        while visible_entries.len() < rows {
+            if let Some(source) = self.header_at(actual_index) {
+                visible_entries.push(self.header_view(source));
+                if visible_entries.len() == rows {
+                    break;
                 }
             }
+            let row_palette = if actual_index >= self.filtered_entries.len() {
+                &palette.row
+            } else if actual_index == self.selected_index {
+                &palette.selected_row
+            } else if actual_index % 2 == 1 {
+                &palette.alternate_row
+            } else {
+                &palette.row
+            };
+            visible_entries.push(self.entry_view(actual_index, row_palette, Length::Fill));
+            actual_index += 1;
         }
-        entries.sort();
-        Ok(entries)
+
+        let entries = container(
+            Column::with_children(visible_entries)
+                .spacing(palette.list.spacing),
+        )
+        .padding(palette.list.padding)
+        .style(theme::Container::Custom(Box::new(style::DarkContainer::new(&palette.list))));
+
+        let mut col = Column::new()
+            .push(self.search_view(Length::Fill))
+            .max_width(self.placement.width as f32)
+            .spacing(palette.window.spacing);
+        if let Some(message) = self.message_view() {
+            col = col.push(message);
+        }
+        col = col.push(self.with_pane(entries));
+
+        if let Some(status) = self.status_view() {
+            col = col.push(status);
+        }
+
+        container(col)
+            .padding(palette.window.padding)
+            .style(theme::Container::Custom(Box::new(style::DarkContainer::new(&palette.window))))
+            .into()
     }
 
-    fn filter_entries(&mut self) {
-        if self.input_value.trim().is_empty() {
-            // Show all applications when input is empty
-            self.filtered_entries = self.entries.clone().into_iter().take(self.config.max_entries).collect();
-            return;
+    // Prompt, input and one page of results on a single line, with arrows where more pages are
+    fn bar_view(&self) -> Element<'_, Message> {
+        let palette = &self.palette;
+        let font_size = self.config.font_size as f32;
+        let input_width = (self.placement.width as f32 * BAR_INPUT_SHARE).max(BAR_INPUT_MIN_WIDTH);
+
+        let arrow_width = text_width("<", &palette.row, font_size) + palette.list.spacing;
+        let mut available = self.placement.width as f32
+            - 2.0 * palette.window.padding
+            - input_width
+            - 2.0 * palette.window.spacing
+            - 2.0 * arrow_width;
+        if let Some(label) = self.prompt_label() {
+            available -= text_width(&label, &palette.prompt, font_size) + palette.prompt.spacing;
         }
+        let widths = (0..self.filtered_entries.len())
+            .map(|index| text_width(&self.display_text(index), &palette.row, font_size) + palette.list.spacing);
+        let (start, end) = page(widths, available, self.selected_index);
 
-        // Check if input is a math expression
-        if let Some(result) = self.evaluate_math(&self.input_value) {
-            self.filtered_entries = vec![format!("Answer: {}", result)];
-            return;
+        let arrow = |visible: bool| styled_text(if visible { "<" } else { " " }, &palette.row, font_size);
+        let mut entries = Row::new()
+            .spacing(palette.list.spacing)
+            .align_items(Alignment::Center)
+            .push(container(arrow(start > 0)).padding(palette.row.padding));
+        for index in start..end {
+            let row_palette = if index == self.selected_index { &palette.selected_row } else { &palette.row };
+            entries = entries.push(self.entry_view(index, row_palette, Length::Shrink));
+        }
+        if end < self.filtered_entries.len() {
+            let more = styled_text(">", &palette.row, font_size);
+            entries = entries.push(container(more).padding(palette.row.padding));
         }
 
-        // Regular fuzzy search
-        let matcher = SkimMatcherV2::default();
-        let mut matches: Vec<_> = self.entries
-            .iter()
-            .filter_map(|entry| {
-                matcher.fuzzy_match(entry, &self.input_value)
-                    .map(|score| (score, entry))
+        let list = container(entries)
+            .width(Length::Fill)
+            .padding(palette.list.padding)
+            .style(theme::Container::Custom(Box::new(style::DarkContainer::new(&palette.list))));
+
+        let mut row = Row::new()
+            .push(self.search_view(Length::Fixed(input_width)))
+            .push(list)
+            .spacing(palette.window.spacing)
+            .align_items(Alignment::Center);
+        if let Some(status) = self.status_view() {
+            row = row.push(status);
+        }
+
+        container(row)
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .center_y()
+            .padding(palette.window.padding)
+            .style(theme::Container::Custom(Box::new(style::DarkContainer::new(&palette.window))))
+            .into()
+    }
+
+    // Tiles with the application's icon above its name, a page of whole rows at a time
+    fn grid_view(&self) -> Element<'_, Message> {
+        let palette = &self.palette;
+        let font_size = self.config.font_size as f32;
+        let columns = self.config.grid_columns.max(1);
+        let (visible, _) = self.visible_entries();
+        let label_chars = (self.tile_width() / (palette.row.text_size.unwrap_or(font_size) * CHAR_WIDTH)) as usize;
+
+        let end = (self.display_start_index + visible).min(self.filtered_entries.len());
+        let indices: Vec<usize> = (self.display_start_index..end).collect();
+        let rows = indices
+            .chunks(columns)
+            .map(|chunk| {
+                let mut row = Row::new().spacing(palette.list.spacing);
+                for &index in chunk {
+                    let row_palette = if index == self.selected_index { &palette.selected_row } else { &palette.row };
+                    row = row.push(self.tile_view(index, row_palette, label_chars));
+                }
+                // A short last row keeps its tiles the width of the others
+                for _ in chunk.len()..columns {
+                    row = row.push(Space::with_width(Length::FillPortion(1)));
+                }
+                row.into()
             })
             .collect();
-        
-        matches.sort_by(|a, b| b.0.cmp(&a.0));
-        self.filtered_entries = matches.into_iter()
-            .map(|(_, entry)| entry.clone())
-            .take(self.config.max_entries)
-            .collect();
-    }
 
-    fn get_settings_options(&self) -> Vec<String> {
-        let mut options = vec!["Back to Main".to_string()];
-        
-        // Add available themes
-        let themes = AppTheme::get_available_themes();
-        for theme in themes {
-            options.push(format!("Theme: {}", theme));
+        let entries = container(Column::with_children(rows).spacing(palette.list.spacing))
+            .width(Length::Fill)
+            .height(Length::FillPortion(3))
+            .padding(palette.list.padding)
+            .style(theme::Container::Custom(Box::new(style::DarkContainer::new(&palette.list))));
+
+        let mut col = Column::new().push(self.search_view(Length::Fill)).spacing(palette.window.spacing);
+        if let Some(message) = self.message_view() {
+            col = col.push(message);
         }
-        
-        // Add current settings info
-        options.push(format!("Current Theme: {}", self.config.theme));
-        options.push(format!("Font Size: {}", self.config.font_size));
-        options.push(format!("Max Entries: {}", self.config.max_entries));
-        
-        options
+        col = col.push(self.with_pane(entries));
+        if let Some(status) = self.status_view() {
+            col = col.push(status);
+        }
+
+        container(col)
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .padding(palette.window.padding)
+            .style(theme::Container::Custom(Box::new(style::DarkContainer::new(&palette.window))))
+            .into()
     }
 
-    fn handle_settings_selection(&mut self, entry: &str) -> Command<Message> {
-        if entry == "Back to Main" {
-            Command::perform(async {}, |_| Message::BackToMain)
-        } else if entry.starts_with("Theme: ") {
-            let theme_name = entry.strip_prefix("Theme: ").unwrap_or("default").to_string();
-            Command::perform(async move { theme_name }, Message::ChangeTheme)
-        } else {
-            Command::none()
-        }
+    fn tile_view(&self, index: usize, row_palette: &ElementPalette, label_chars: usize) -> Element<'_, Message> {
+        let font_size = self.config.font_size as f32;
+        let entry = self.filtered_entries.get(index).map(|s| s.as_str()).unwrap_or("");
+        let size = Length::Fixed(self.icons.size() as f32);
+
+        let icon: Element<_> = match self.icons.get(entry) {
+            Some(Icon::Raster(handle)) => image(handle.clone()).width(size).height(size).into(),
+            Some(Icon::Vector(handle)) => svg(handle.clone()).width(size).height(size).into(),
+            // Entries without an icon, or whose icon is still loading, get their initial on a plain square
+            None => {
+                let initial: String = entry.chars().take(1).flat_map(char::to_uppercase).collect();
+                container(styled_text(initial, &self.palette.prompt, self.icons.size() as f32 / 2.0))
+                    .width(size)
+                    .height(size)
+                    .center_x()
+                    .center_y()
+                    .style(theme::Container::Custom(Box::new(style::DarkContainer::new(&self.palette.prompt))))
+                    .into()
+            }
+        };
+
+        let content = Column::new()
+            .push(icon)
+            .push(styled_text(truncate(entry, label_chars), row_palette, font_size))
+            .spacing(TILE_GAP)
+            .align_items(Alignment::Center);
+        let tile = container(content)
+            .width(Length::Fill)
+            .center_x()
+            .padding(row_palette.padding)
+            .style(theme::Container::Custom(Box::new(style::DarkContainer::new(row_palette))));
+
+        container(mouse_area(tile).on_press(Message::EntrySelected(index)))
+            .width(Length::FillPortion(1))
+            .into()
     }
 
-    fn execute_command(&self, cmd: &str) -> Result<()> {
-        ProcessCommand::new("sh")
-            .arg("-c")
-            .arg(cmd)
-            .spawn()?;
-        Ok(())
+    // Room for a label inside one tile
+    fn tile_width(&self) -> f32 {
+        let palette = &self.palette;
+        let columns = self.config.grid_columns.max(1) as f32;
+        let inner = self.results_area().0 - 2.0 * palette.list.padding - (columns - 1.0) * palette.list.spacing;
+        inner / columns - 2.0 * palette.row.padding
     }
 
-    fn move_selection(&mut self, delta: isize) {
-        if !self.filtered_entries.is_empty() {
-            let len = self.filtered_entries.len();
-            self.selected_index = if delta < 0 && self.selected_index == 0 {
-                len - 1
-            } else if delta > 0 && self.selected_index == len - 1 {
-                0
-            } else {
-                let new_index = (self.selected_index as isize + delta) as usize;
-                if new_index < len {
-                    new_index
-                } else if delta > 0 {
-                    0
-                } else {
-                    len - 1
-                }
-            };
+    // How many rows of tiles fit below the input
+    fn grid_rows(&self) -> usize {
+        let palette = &self.palette;
+        let font_size = self.config.font_size as f32;
+        let available = self.results_area().1 - 2.0 * palette.list.padding;
+        let tile = self.icons.size() as f32 + TILE_GAP + line_height(&palette.row, font_size);
+        (((available + palette.list.spacing) / (tile + palette.list.spacing)) as usize).max(1)
+    }
 
-            // Adjust display window to keep selected item visible
-            let max_visible = self.config.max_entries;
-            if self.selected_index < self.display_start_index {
-                self.display_start_index = self.selected_index;
-            } else if self.selected_index >= self.display_start_index + max_visible {
-                self.display_start_index = self.selected_index.saturating_sub(max_visible - 1);
+    // Results shown at once, and how far scrolling moves
+    fn visible_entries(&self) -> (usize, usize) {
+        match self.layout() {
+            Layout::Grid => {
+                let columns = self.config.grid_columns.max(1);
+                (self.grid_rows() * columns, columns)
             }
+            Layout::List => (self.list_rows(), 1),
+            Layout::Bar => (self.config.max_entries, 1),
         }
     }
 
-    fn evaluate_math(&self, input: &str) -> Option<f64> {
-        let input = input.trim().replace(" ", "");
-        
-        // Simple math parser for basic operations
-        if let Some(pos) = input.find('+') {
-            let (left, right) = input.split_at(pos);
-            let right = &right[1..]; // skip the '+'
-            if let (Ok(a), Ok(b)) = (left.parse::<f64>(), right.parse::<f64>()) {
-                return Some(a + b);
-            }
+    // Settings entries are text, so a grid shows them as a list
+    fn layout(&self) -> Layout {
+        match self.config.layout {
+            Layout::Grid if self.mode == Mode::Settings => Layout::List,
+            layout => layout,
         }
-        
-        if let Some(pos) = input.find('-') {
-            if pos > 0 { // Don't treat negative numbers as subtraction
-                let (left, right) = input.split_at(pos);
-                let right = &right[1..]; // skip the '-'
-                if let (Ok(a), Ok(b)) = (left.parse::<f64>(), right.parse::<f64>()) {
-                    return Some(a - b);
-                }
-            }
+    }
+
+    // The results beside or above the preview pane, when it is shown
+    fn with_pane<'a>(&'a self, results: Container<'a, Message>) -> Element<'a, Message> {
+        if !self.show_pane() {
+            return results.into();
         }
-        
-        if let Some(pos) = input.find('*') {
-            let (left, right) = input.split_at(pos);
-            let right = &right[1..]; // skip the '*'
-            if let (Ok(a), Ok(b)) = (left.parse::<f64>(), right.parse::<f64>()) {
-                return Some(a * b);
-            }
+        let spacing = self.palette.window.spacing;
+        match self.config.preview {
+            PreviewPosition::Right => Row::new()
+                .push(results.width(Length::FillPortion(3)))
+                .push(self.pane_view().width(Length::FillPortion(2)).height(Length::Fill))
+                .spacing(spacing)
+                .into(),
+            PreviewPosition::Bottom => Column::new()
+                .push(results)
+                .push(self.pane_view().width(Length::Fill).height(Length::FillPortion(2)))
+                .spacing(spacing)
+                .into(),
+            PreviewPosition::Off => results.into(),
+        }
+    }
+
+    // The loaded preview, cut to the lines that fit the pane
+    fn pane_view(&self) -> Container<'_, Message> {
+        let palette = &self.palette;
+        let font_size = self.config.font_size as f32;
+        let text_size = palette.row.text_size.unwrap_or(font_size);
+        let (width, height) = self.pane_size();
+
+        let mut content = Column::new().spacing(TILE_GAP).align_items(Alignment::Center);
+        if let Some(loaded) = &self.pane.content {
+            let mut lines = ((height - 2.0 * palette.list.padding) / (text_size * LINE_HEIGHT)).max(1.0) as usize;
+            match &loaded.image {
+                Some(Icon::Raster(handle)) => content = content.push(image(handle.clone()).width(Length::Fill).height(Length::Fill)),
+                Some(Icon::Vector(handle)) => content = content.push(svg(handle.clone()).width(Length::Fill).height(Length::Fill)),
+                None => {}
+            }
+            // An image leaves a line for its caption
+            if loaded.image.is_some() {
+                lines = 1;
+            }
+            let chars = ((width - 2.0 * palette.list.padding) / (text_size * CHAR_WIDTH)).max(1.0) as usize;
+            let text: Vec<String> = loaded
+                .text
+                .lines()
+                .take(lines)
+                .map(|line| truncate(&line.replace('\t', "    "), chars))
+                .collect();
+            content = content.push(
+                container(styled_text(text.join("\n"), &palette.row, font_size)).width(Length::Fill),
+            );
+        }
+
+        container(content)
+            .padding(palette.list.padding)
+            .style(theme::Container::Custom(Box::new(style::DarkContainer::new(&palette.list))))
+    }
+
+    fn show_pane(&self) -> bool {
+        self.config.preview != PreviewPosition::Off && self.mode != Mode::Settings && self.layout() != Layout::Bar
+    }
+
+    // The window below the input, less the status line
+    fn body_size(&self) -> (f32, f32) {
+        let palette = &self.palette;
+        let font_size = self.config.font_size as f32;
+        let mut height = self.placement.height as f32
+            - 2.0 * palette.window.padding
+            - search_height(palette, font_size)
+            - palette.window.spacing;
+        if self.status.is_some() {
+            height -= palette.window.spacing + line_height(&palette.status_bar, font_size);
+        }
+        height -= message_height(palette, font_size, self.extras().message_lines);
+        (self.placement.width as f32 - 2.0 * palette.window.padding, height)
+    }
+
+    // Room for the results once the preview pane has its share
+    fn results_area(&self) -> (f32, f32) {
+        let (width, height) = self.body_size();
+        let spacing = self.palette.window.spacing;
+        match self.config.preview {
+            _ if !self.show_pane() => (width, height),
+            PreviewPosition::Right => ((width - spacing) * RESULTS_SHARE, height),
+            PreviewPosition::Bottom => (width, (height - spacing) * RESULTS_SHARE),
+            PreviewPosition::Off => (width, height),
+        }
+    }
+
+    // Rows of the list; a fixed-size window shares its height with a pane below
+    fn list_rows(&self) -> usize {
+        let rows = list_rows(&self.config, self.filtered_entries.len() + self.header_count());
+        if self.config.auto_height || !self.show_pane() || self.config.preview != PreviewPosition::Bottom {
+            return rows;
+        }
+        let palette = &self.palette;
+        let row = line_height(&palette.row, self.config.font_size as f32);
+        let available = self.results_area().1 - 2.0 * palette.list.padding;
+        rows.min((((available + palette.list.spacing) / (row + palette.list.spacing)) as usize).max(1))
+    }
+
+    // Rough size of the preview pane, for cutting its text to what fits
+    fn pane_size(&self) -> (f32, f32) {
+        let (width, height) = self.body_size();
+        let spacing = self.palette.window.spacing;
+        match (self.config.preview, self.layout()) {
+            (PreviewPosition::Right, _) => ((width - spacing) * (1.0 - RESULTS_SHARE), height),
+            // The list keeps its rows and the pane gets the rest
+            (_, Layout::List) => {
+                let rows = self.list_rows();
+                let list = list_height(&self.config, &self.palette, rows);
+                (width, height - spacing - list)
+            }
+            _ => (width, (height - spacing) * (1.0 - RESULTS_SHARE)),
+        }
+    }
+
+    // Starts loading the preview when the selection moves, cancelling the load still running
+    fn update_pane(&mut self) -> Command<Message> {
+        let entry = if self.show_pane() { self.filtered_entries.get(self.selected_index).cloned() } else { None };
+        if entry == self.pane.entry {
+            return Command::none();
+        }
+        if let Some(task) = self.pane.task.take() {
+            task.abort();
+        }
+        self.pane.generation += 1;
+        self.pane.content = None;
+        self.pane.entry = entry;
+
+        let Some(target) = self.pane.entry.as_deref().and_then(|entry| self.preview_target(self.selected_index, entry)) else {
+            return Command::none();
+        };
+        let (load, task) = abortable(preview::load(target));
+        self.pane.task = Some(task);
+        let generation = self.pane.generation;
+        Command::perform(load, move |content| Message::PreviewLoaded(generation, content.ok()))
+    }
+
+    // Icons of the tiles on screen that have not been looked up yet
+    fn load_icons(&mut self) -> Command<Message> {
+        if self.layout() != Layout::Grid {
+            return Command::none();
+        }
+        let (visible, _) = self.visible_entries();
+        let end = (self.display_start_index + visible).min(self.filtered_entries.len());
+        let shown = self.filtered_entries.get(self.display_start_index..end).unwrap_or_default();
+        let size = self.icons.size();
+        match self.icons.load(shown) {
+            Some(load) => Command::perform(load, move |loaded| Message::IconsLoaded(size, loaded)),
+            None => Command::none(),
+        }
+    }
+
+    fn preview_target(&self, index: usize, entry: &str) -> Option<Target> {
+        match self.mode {
+            Mode::Apps if self.source_of(index) == Some(Source::Apps) => Some(Target::App {
+                name: entry.to_string(),
+                search_paths: self.config.search_paths.clone(),
+            }),
+            Mode::Dmenu => Target::for_entry(entry, &self.config.preview_command),
+            _ => None,
+        }
+    }
+
+    fn search_view(&self, input_width: Length) -> Row<'_, Message> {
+        let palette = &self.palette;
+        let font_size = self.config.font_size as f32;
+        let input = TextInput::new(
+            &self.placeholder(),
+            &self.input_value,
+        )
+        .on_input(Message::InputChanged)
+        .padding(palette.input.padding)
+        .font(palette.input.font)
+        .size(palette.input.text_size.unwrap_or(font_size))
+        .width(input_width)
+        .style(theme::TextInput::Custom(Box::new(style::DarkTextInput::new(&palette.input))));
+
+        let mut search = Row::new()
+            .spacing(palette.prompt.spacing)
+            .align_items(Alignment::Center);
+        if let Some(label) = self.prompt_label() {
+            search = search.push(
+                container(styled_text(label, &palette.prompt, font_size))
+                    .padding(palette.prompt.padding)
+                    .style(theme::Container::Custom(Box::new(style::DarkContainer::new(&palette.prompt)))),
+            );
+        }
+        search.push(input)
+    }
+
+    // Settings entries get a gear, except for the themes
+    fn display_text(&self, index: usize) -> String {
+        let entry = self.filtered_entries.get(index).map(|s| s.as_str()).unwrap_or("");
+        let setting = match self.mode {
+            Mode::Settings => self.editor().is_none(),
+            _ => self.source_of(index) == Some(Source::Settings),
+        };
+        if setting && !entry.is_empty() && !entry.starts_with("Theme: ") {
+            format!("⚙ {}", entry)
+        } else {
+            entry.to_string()
+        }
+    }
+
+    // One result; indices past the end make an empty row so the list keeps its height
+    fn entry_view(&self, index: usize, row_palette: &ElementPalette, width: Length) -> Element<'_, Message> {
+        let font_size = self.config.font_size as f32;
+        let entry_text = self.filtered_entries.get(index).map(|s| s.as_str()).unwrap_or("");
+        let display_text = self.display_text(index);
+
+        let swatch = self.editor().and_then(|editor| editor.swatch(entry_text));
+        let content: Element<_> = match swatch {
+            Some(color) => Row::new()
+                .push(
+                    container(Space::new(Length::Fixed(14.0), Length::Fixed(14.0)))
+                        .style(theme::Container::Custom(Box::new(style::DarkContainer {
+                            bg_color: color,
+                            text_color: row_palette.text,
+                            border_color: Some(row_palette.text),
+                            border_width: 1.0,
+                            border_radius: 2.0,
+                        }))),
+                )
+                .push(styled_text(display_text, row_palette, font_size))
+                .spacing(8)
+                .align_items(Alignment::Center)
+                .into(),
+            None => styled_text(display_text, row_palette, font_size).into(),
+        };
+
+        let row = container(content)
+            .width(width)
+            .padding(row_palette.padding)
+            .style(theme::Container::Custom(Box::new(style::DarkContainer::new(row_palette))));
+
+        mouse_area(row)
+            .on_press(Message::EntrySelected(index))
+            .into()
+    }
+
+    // The `message` block between the input and the results
+    fn message_view(&self) -> Option<Element<'_, Message>> {
+        let palette = &self.palette;
+        let message = self.message();
+        if message.is_empty() {
+            return None;
+        }
+        Some(
+            container(styled_text(message, &palette.prompt, self.config.font_size as f32))
+                .width(Length::Fill)
+                .padding(palette.prompt.padding)
+                .style(theme::Container::Custom(Box::new(style::DarkContainer::new(&palette.prompt))))
+                .into(),
+        )
+    }
+
+    fn header_view(&self, source: Source) -> Element<'_, Message> {
+        let palette = &self.palette;
+        let size = palette.row.text_size.unwrap_or(self.config.font_size as f32);
+        container(styled_text(source.label(), &palette.prompt, size).size(size))
+            .width(Length::Fill)
+            .padding(palette.row.padding)
+            .into()
+    }
+
+    fn status_view(&self) -> Option<Element<'_, Message>> {
+        let palette = &self.palette;
+        let status = self.status.as_ref()?;
+        Some(
+            container(styled_text(status.as_str(), &palette.status_bar, self.config.font_size as f32))
+                .padding(palette.status_bar.padding)
+                .style(theme::Container::Custom(Box::new(style::DarkContainer::new(&palette.status_bar))))
+                .into(),
+        )
+    }
+
+    fn load_applications(search_paths: &[PathBuf]) -> Result<Vec<String>> {
+        let mut entries = Vec::new();
+        for path in search_paths {
+            if let Ok(entries_in_path) = std::fs::read_dir(path) {
+                for entry in entries_in_path.filter_map(Result::ok) {
+                    if let Ok(file_type) = entry.file_type() {
+                        if file_type.is_file() {
+                            if let Ok(metadata) = entry.metadata() {
+                                if metadata.permissions().mode() & 0o111 != 0 {
+                                    if let Some(name) = entry.file_name().to_str() {
+                                        entries.push(name.to_string());
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        entries.sort();
+        Ok(entries)
+    }
+
+    fn set_query(&mut self, value: String) {
+        self.input_value = value;
+
+        // Typing `> Settings` opens settings like Ctrl+, does
+        let query = self.input_value.trim();
+        if self.mode != Mode::Settings && (query == "> Settings" || query == ">Settings") {
+            self.set_mode(Mode::Settings);
+            return;
+        }
+
+        if self.mode == Mode::Settings {
+            self.show_screen();
+        } else {
+            self.filter_entries();
+        }
+        self.selected_index = 0;
+        self.display_start_index = 0;
+    }
+
+    fn set_mode(&mut self, mode: Mode) {
+        // Leaving settings drops unsaved theme edits, wherever they are on the stack
+        let editor = self.screens.drain(..).find_map(|screen| match screen {
+            Screen::Editor(editor) => Some(editor),
+            _ => None,
+        });
+        if let Some(editor) = editor {
+            let _ = self.set_theme(editor.original);
+        }
+        if mode != Mode::Dmenu {
+            self.selection_reply = None;
+        }
+
+        if mode == Mode::Settings {
+            if self.mode != Mode::Settings {
+                self.previous_mode = self.mode;
+            }
+            self.themes = AppTheme::get_available_themes(&self.config.theme_paths);
+            self.screens.push(Screen::Settings);
+        }
+        self.mode = mode;
+        self.input_value.clear();
+        if mode == Mode::Settings {
+            self.show_screen();
+        } else {
+            self.filter_entries();
+        }
+        self.selected_index = 0;
+        self.display_start_index = 0;
+    }
+
+    fn handle_request(&mut self, request: Request, reply: Option<ipc::Reply>) -> Command<Message> {
+        match request {
+            Request::Show => self.show(),
+            Request::Hide => self.hide(),
+            Request::Toggle => {
+                if self.visible {
+                    self.hide()
+                } else {
+                    self.show()
+                }
+            }
+            Request::SetMode { mode } => {
+                self.set_mode(mode);
+                Command::none()
+            }
+            Request::SetQuery { query } => {
+                self.set_query(query);
+                Command::none()
+            }
+            Request::SetPrompt { prompt, mode } => {
+                self.texts.set_prompt(mode, prompt);
+                Command::none()
+            }
+            Request::SetPlaceholder { placeholder } => {
+                self.texts.placeholder = Some(placeholder);
+                Command::none()
+            }
+            Request::SetMessage { message } => {
+                self.texts.message = Some(message);
+                Command::none()
+            }
+            Request::PushEntries { entries } => {
+                self.dmenu_entries = entries;
+                self.selection_reply = reply;
+                self.set_mode(Mode::Dmenu);
+                self.show()
+            }
+            Request::ReloadConfig => self.reload_config(),
+            Request::ReloadTheme => self.reload_theme(),
+            Request::Quit => match reply {
+                Some(reply) => Command::perform(reply.send(String::new()), |()| Message::Quit),
+                None => std::process::exit(0),
+            },
+        }
+    }
+
+    // Keeps the last good configuration and reports the error when a file fails to parse.
+    // Errors the current configuration already had, such as a bad FMENU_* variable, are
+    // left out again rather than blocking every reload
+    fn reload_config(&mut self) -> Command<Message> {
+        let layered = match Config::load_lenient() {
+            Ok(layered) => layered,
+            Err(e) => {
+                self.set_error("config.toml", e.into());
+                return Command::none();
+            }
+        };
+        layered.print_warnings();
+        if let Some(error) = layered.errors.iter().find(|e| !self.config_errors.contains(e)) {
+            self.set_error("config", anyhow::anyhow!(error.clone()));
+            return Command::none();
+        }
+        let (config, errors, warnings) = (layered.config, layered.errors, layered.warnings);
+        let theme_name = self.theme_override.as_deref().unwrap_or(&config.theme);
+        let theme = match AppTheme::load(theme_name, &config.theme_paths) {
+            Ok(theme) => theme,
+            Err(e) => {
+                self.set_error(&format!("theme {}", theme_name), e);
+                return Command::none();
+            }
+        };
+
+        if config.search_paths != self.config.search_paths {
+            self.entries = Self::load_applications(&config.search_paths).unwrap_or_default();
+        }
+        if config.icon_size != self.config.icon_size {
+            self.icons = Icons::new(config.icon_size);
+        }
+        self.config = config;
+        self.themes = AppTheme::get_available_themes(&self.config.theme_paths);
+        // The preview command may have changed
+        self.pane.entry = None;
+        let load_fonts = self.apply_reloaded_theme(theme);
+        self.status = config_status(&errors, &warnings);
+        self.config_errors = errors;
+        self.refresh();
+
+        Command::batch([load_fonts, self.place_window(false)])
+    }
+
+    fn reload_theme(&mut self) -> Command<Message> {
+        let theme_name = self.theme_name().to_string();
+        match AppTheme::load(&theme_name, &self.config.theme_paths) {
+            Ok(theme) => {
+                self.status = None;
+                self.apply_reloaded_theme(theme)
+            }
+            Err(e) => {
+                self.set_error(&format!("theme {}", theme_name), e);
+                Command::none()
+            }
+        }
+    }
+
+    // An open editor keeps showing its working copy; the reloaded theme is what closing it restores
+    fn apply_reloaded_theme(&mut self, theme: AppTheme) -> Command<Message> {
+        self.preview = None;
+        let editor = self.screens.iter_mut().find_map(|screen| match screen {
+            Screen::Editor(editor) => Some(editor),
+            _ => None,
+        });
+        match editor {
+            Some(editor) => {
+                editor.original = theme;
+                Command::none()
+            }
+            None => self.set_theme(theme),
+        }
+    }
+
+    // The mode's prompt; in settings, breadcrumbs of the screens such as
+    // `Settings › search_paths › Add Path`
+    fn prompt_label(&self) -> Option<String> {
+        if self.screens.is_empty() {
+            return Some(self.prompt(self.mode).to_string()).filter(|p| !p.is_empty());
+        }
+        let titles: Vec<String> = std::iter::once(self.prompt(Mode::Settings).to_string())
+            .filter(|p| !p.is_empty())
+            .chain(self.screens.iter().skip(1).map(Screen::title))
+            .collect();
+        Some(titles.join(" › ")).filter(|label| !label.is_empty())
+    }
+
+    fn prompt(&self, mode: Mode) -> &str {
+        if let Some(prompt) = self.texts.prompts.get(&mode) {
+            return prompt;
+        }
+        match mode {
+            Mode::Apps => &self.config.prompts.apps,
+            Mode::Settings => &self.config.prompts.settings,
+            Mode::Dmenu => &self.config.prompts.dmenu,
+        }
+    }
+
+    fn message(&self) -> &str {
+        self.texts.message.as_deref().unwrap_or(&self.config.message)
+    }
+
+    fn extras(&self) -> Extras {
+        Extras {
+            status: self.status.is_some(),
+            pane: self.show_pane() && self.config.preview == PreviewPosition::Bottom,
+            message_lines: self.message().lines().count(),
+        }
+    }
+
+    fn placeholder(&self) -> String {
+        match self.screens.last() {
+            Some(Screen::Input(SettingInput::Value(key))) => match settings::kind(key) {
+                Some(settings::Kind::Number) => {
+                    if let Some((min, max)) = config::range(key) {
+                        return format!("A number from {} to {}", min, max);
+                    }
+                }
+                Some(settings::Kind::Dimension) => {
+                    if let Some((min, max)) = config::range(key) {
+                        return format!("Pixels from {} to {}, or a percentage like 50%", min, max);
+                    }
+                }
+                Some(settings::Kind::Text) => {
+                    return match *key {
+                        "sources" => "Sources in order, from calculator, apps and settings",
+                        "preview_command" => "Shell command; {} stands for the selected entry",
+                        _ => "Text, or nothing to leave it out",
+                    }
+                    .to_string();
+                }
+                _ => {}
+            },
+            Some(Screen::Input(SettingInput::AddPath(_))) => return "Directory to add".to_string(),
+            Some(Screen::Input(SettingInput::Terminal)) => return "Terminal command".to_string(),
+            _ => {}
+        }
+        match self.editor().and_then(|e| e.editing.as_ref().map(|editing| (e, editing))) {
+            Some((editor, Editing::Field { key, .. })) => editor.hint(key).to_string(),
+            Some((_, Editing::SaveName)) => "Name of the new theme".to_string(),
+            None => self.texts.placeholder.as_deref().unwrap_or(&self.config.placeholder).to_string(),
+        }
+    }
+
+    fn theme_name(&self) -> &str {
+        self.theme_override.as_deref().unwrap_or(&self.config.theme)
+    }
+
+    fn set_theme(&mut self, theme: AppTheme) -> Command<Message> {
+        self.palette = Palette::from(&theme);
+        self.theme = theme;
+        let load_fonts = self.load_fonts();
+        // The bar is as tall as the theme's text and padding make it
+        let height = self.content_height();
+        if height.is_some_and(|h| h != self.placement.height) && !self.placement.fullscreen {
+            return Command::batch([load_fonts, self.place_window(false)]);
+        }
+        load_fonts
+    }
+
+    fn load_fonts(&mut self) -> Command<Message> {
+        let mut commands = Vec::new();
+        for path in &self.theme.font_files {
+            if self.loaded_fonts.contains(path) {
+                continue;
+            }
+            match std::fs::read(path) {
+                Ok(bytes) => {
+                    self.loaded_fonts.insert(path.clone());
+                    commands.push(font::load(bytes).map(Message::FontLoaded));
+                }
+                Err(e) => log::error!("Failed to read font {}: {}", path.display(), e),
+            }
+        }
+        Command::batch(commands)
+    }
+
+    // Follows the selection in the settings theme list; anything else shows the committed theme
+    fn preview_selected(&mut self) -> Command<Message> {
+        let selected = match self.mode {
+            Mode::Settings => self
+                .filtered_entries
+                .get(self.selected_index)
+                .and_then(|entry| entry.strip_prefix("Theme: "))
+                .filter(|name| *name != self.theme_name())
+                .map(str::to_string),
+            _ => None,
+        };
+        let Some(name) = selected else {
+            self.revert_preview();
+            return Command::none();
+        };
+        if self.preview.as_ref().is_some_and(|p| p.name == name) {
+            return Command::none();
+        }
+
+        let (previous, status) = match self.preview.take() {
+            Some(preview) => (preview.previous, preview.status),
+            None => (self.theme.clone(), self.status.clone()),
+        };
+        let result = AppTheme::load(&name, &self.config.theme_paths);
+        self.preview = Some(Preview { name: name.clone(), previous, status });
+        match result {
+            Ok(theme) => self.set_theme(theme),
+            Err(e) => {
+                log::error!("Failed to load theme {}: {:#}", name, e);
+                self.status = Some(format!("Error in theme {}: {:#}", name, e));
+                Command::none()
+            }
+        }
+    }
+
+    fn revert_preview(&mut self) {
+        if let Some(preview) = self.preview.take() {
+            // Fonts of the committed theme were loaded when it was first applied
+            let _ = self.set_theme(preview.previous);
+            self.status = preview.status;
+        }
+    }
+
+    // After Escape the selection goes back to the committed theme so it is not previewed again
+    fn select_current_theme(&mut self) {
+        let current = format!("Theme: {}", self.theme_name());
+        self.select_entry(|entry| entry == current);
+    }
+
+    fn select_entry(&mut self, matches: impl Fn(&str) -> bool) {
+        if let Some(index) = self.filtered_entries.iter().position(|e| matches(e)) {
+            self.move_selection(index as isize - self.selected_index as isize);
+        }
+    }
+
+    fn change_theme(&mut self, name: String) -> Command<Message> {
+        self.preview = None;
+        match AppTheme::load(&name, &self.config.theme_paths) {
+            Ok(theme) => {
+                self.theme_override = None;
+                self.config.theme = name;
+                self.set_theme(theme)
+            }
+            Err(e) => {
+                self.set_error(&format!("theme {}", name), e);
+                Command::none()
+            }
+        }
+    }
+
+    fn save_config(&mut self, key: &str) {
+        if let Err(e) = self.config.save_key(key) {
+            log::error!("Failed to save config: {}", e);
+            self.status = Some(format!("Failed to save config: {}", e));
+        }
+    }
+
+    fn set_error(&mut self, source: &str, error: anyhow::Error) {
+        log::error!("Failed to reload {}: {:#}", source, error);
+        self.status = Some(format!("Error in {}: {:#}", source, error));
+    }
+
+    // Re-run the current query, keeping the selection where possible
+    fn refresh(&mut self) {
+        let selected = self.selected_index;
+        let start = self.display_start_index;
+        if self.mode == Mode::Settings {
+            self.show_screen();
+        } else {
+            self.filter_entries();
+        }
+        if selected < self.filtered_entries.len() {
+            self.selected_index = selected;
+            self.display_start_index = start.min(selected);
+        }
+    }
+
+    // The pointer and the focused monitor move between shows, so placement is resolved again
+    fn show(&mut self) -> Command<Message> {
+        self.visible = true;
+        self.place_window(true)
+    }
+
+    // xrandr and xdotool may take a while, so they run on a blocking thread rather than
+    // holding up the UI
+    fn place_window(&mut self, showing: bool) -> Command<Message> {
+        let config = self.config.clone();
+        let content_height = self.content_height();
+        Command::perform(
+            async move { tokio::task::spawn_blocking(move || Placement::resolve(&config, content_height)).await.ok() },
+            move |placement| Message::Placed(placement, showing),
+        )
+    }
+
+    fn move_window(&mut self, placement: Placement) -> Command<Message> {
+        self.placement = placement;
+        let mut commands = vec![window::resize(iced::Size::new(self.placement.width, self.placement.height))];
+        if let Some((x, y)) = self.placement.position {
+            commands.push(window::move_to(x, y));
+        }
+        if self.visible {
+            commands.push(window::change_mode(self.placement.mode()));
+        }
+        Command::batch(commands)
+    }
+
+    // With `auto_height` the list's window grows and shrinks with the results
+    fn fit_height(&mut self) -> Command<Message> {
+        if self.layout() != Layout::List || !self.config.auto_height || self.placement.fullscreen {
+            return Command::none();
+        }
+        let Some(height) = self.content_height().filter(|h| *h != self.placement.height) else {
+            return Command::none();
+        };
+        self.placement = self.placement.with_height(&self.config, height);
+        let mut commands = vec![window::resize(iced::Size::new(self.placement.width, height))];
+        if let Some((x, y)) = self.placement.position {
+            commands.push(window::move_to(x, y));
+        }
+        Command::batch(commands)
+    }
+
+    fn content_height(&self) -> Option<u32> {
+        let lines = self.filtered_entries.len() + self.header_count();
+        content_height(&self.config, &self.palette, lines, self.extras())
+    }
+
+    fn hide(&mut self) -> Command<Message> {
+        // A client still waiting for a selection is told there is none
+        self.selection_reply = None;
+        self.visible = false;
+        window::change_mode(window::Mode::Hidden)
+    }
+
+    // Close the menu after a selection or Escape; a daemon instance only hides
+    fn dismiss(&mut self) -> Command<Message> {
+        if !self.daemon {
+            std::process::exit(0);
+        }
+        self.set_mode(Mode::Apps);
+        self.hide()
+    }
+
+    fn filter_entries(&mut self) {
+        self.sections.clear();
+
+        // The bar and the grid page through every match instead of showing the first few
+        let limit = match self.layout() {
+            Layout::List => self.config.max_entries,
+            Layout::Bar | Layout::Grid => usize::MAX,
+        };
+
+        if self.mode == Mode::Dmenu {
+            self.filtered_entries = self.search(&self.dmenu_entries);
+            self.filtered_entries.truncate(limit);
+            return;
+        }
+
+        // Each source adds its results in the configured order, up to its own limit
+        let mut results = Vec::new();
+        for source in self.config.sources.clone() {
+            let mut matches = self.source_results(source);
+            let source_limit = self.config.source_limits.get(source);
+            if source_limit > 0 {
+                matches.truncate(source_limit);
+            }
+            matches.truncate(limit - results.len());
+            if !matches.is_empty() {
+                self.sections.push((results.len(), source));
+                results.extend(matches);
+            }
+        }
+        self.filtered_entries = results;
+    }
+
+    fn source_results(&self, source: Source) -> Vec<String> {
+        match source {
+            Source::Apps => self.search(&self.entries),
+            Source::Calculator => self
+                .evaluate_math(&self.input_value)
+                .map(|result| vec![format!("Answer: {}", result)])
+                .unwrap_or_default(),
+            // Settings only show up once something is typed
+            Source::Settings if self.input_value.trim().is_empty() => Vec::new(),
+            Source::Settings => {
+                let mut options = self.get_settings_options();
+                options.retain(|option| option != BACK_TO_MAIN);
+                self.search(&options)
+            }
+        }
+    }
+
+    // Entries matching the query, best first; all of them when there is no query
+    fn search(&self, entries: &[String]) -> Vec<String> {
+        if self.input_value.trim().is_empty() {
+            return entries.to_vec();
+        }
+        let matcher = self.matcher();
+        let mut matches: Vec<_> = entries
+            .iter()
+            .filter_map(|entry| {
+                matcher.fuzzy_match(entry, &self.input_value)
+                    .map(|score| (score, entry))
+            })
+            .collect();
+
+        matches.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+        matches.into_iter().map(|(_, entry)| entry.clone()).collect()
+    }
+
+    // The source the result at `index` came from
+    fn source_of(&self, index: usize) -> Option<Source> {
+        self.sections.iter().rev().find(|(start, _)| *start <= index).map(|(_, source)| *source)
+    }
+
+    // The section whose header goes above the result at `index`; headers are only drawn in the list
+    fn header_at(&self, index: usize) -> Option<Source> {
+        if !self.config.group_results || self.layout() != Layout::List || index >= self.filtered_entries.len() {
+            return None;
+        }
+        self.sections.iter().find(|(start, _)| *start == index).map(|(_, source)| *source)
+    }
+
+    fn header_count(&self) -> usize {
+        if self.config.group_results && self.layout() == Layout::List {
+            self.sections.len()
+        } else {
+            0
+        }
+    }
+
+    // Lines the results from `start` up to `end` take in the list, headers included
+    fn lines_between(&self, start: usize, end: usize) -> usize {
+        (start..end).filter(|index| self.header_at(*index).is_some()).count() + end - start
+    }
+
+    // Tab and Shift+Tab: the first result of the next or previous section
+    fn jump_section(&mut self, forward: bool) {
+        let count = self.sections.len();
+        let current = self.sections.iter().rposition(|(start, _)| *start <= self.selected_index).unwrap_or(0);
+        let target = if forward { (current + 1) % count } else { (current + count - 1) % count };
+        let (start, _) = self.sections[target];
+        self.move_selection(start as isize - self.selected_index as isize);
+    }
+
+    // Runs the result at `index`. A settings result found from apps mode opens settings on it
+    fn activate(&mut self, index: usize) -> Command<Message> {
+        let Some(entry) = self.filtered_entries.get(index).cloned() else {
+            return Command::none();
+        };
+        self.selected_index = index;
+        if self.mode == Mode::Settings {
+            return self.handle_settings_selection(&entry);
+        }
+        match self.source_of(index) {
+            Some(Source::Settings) => {
+                self.set_mode(Mode::Settings);
+                self.select_entry(|e| e == entry);
+                self.handle_settings_selection(&entry)
+            }
+            // Calculator answers are there to be read, not run
+            Some(Source::Calculator) => Command::none(),
+            _ => Command::perform(async { entry }, Message::Execute),
+        }
+    }
+
+    fn matcher(&self) -> SkimMatcherV2 {
+        if self.config.case_sensitive {
+            SkimMatcherV2::default().respect_case()
+        } else {
+            SkimMatcherV2::default().ignore_case()
+        }
+    }
+
+    fn get_settings_options(&self) -> Vec<String> {
+        let mut options = vec![BACK_TO_MAIN.to_string()];
+        
+        // Add available themes
+        for theme in &self.themes {
+            options.push(format!("Theme: {}", theme));
+        }
+        options.push(EDIT_THEME.to_string());
+        
+        for (key, _) in settings::FIELDS {
+            options.push(settings::entry(&self.config, key));
+        }
+        
+        options
+    }
+
+    fn editor(&self) -> Option<&ThemeEditor> {
+        match self.screens.last() {
+            Some(Screen::Editor(editor)) => Some(editor),
+            _ => None,
+        }
+    }
+
+    fn editor_mut(&mut self) -> Option<&mut ThemeEditor> {
+        match self.screens.last_mut() {
+            Some(Screen::Editor(editor)) => Some(editor),
+            _ => None,
+        }
+    }
+
+    // Entries of the innermost screen that has a list of its own, narrowed by the query
+    // unless a value is being typed in
+    fn show_screen(&mut self) {
+        self.sections.clear();
+        let entries = match self.screens.iter().rev().find(|s| !matches!(s, Screen::Input(_))) {
+            Some(Screen::Settings) => self.get_settings_options(),
+            Some(Screen::Terminals(detected)) => {
+                let mut entries = vec![BACK_TO_SETTINGS.to_string()];
+                let mut terminals = detected.clone();
+                if !terminals.contains(&self.config.terminal) {
+                    terminals.insert(0, self.config.terminal.clone());
+                }
+                entries.extend(terminals);
+                entries.push(OTHER_TERMINAL.to_string());
+                entries
+            }
+            Some(Screen::Choices(_, choices)) => {
+                let mut entries = vec![BACK_TO_SETTINGS.to_string()];
+                entries.extend(choices.iter().cloned());
+                entries
+            }
+            Some(Screen::Paths(key)) => {
+                let mut entries = vec![BACK_TO_SETTINGS.to_string(), ADD_PATH.to_string()];
+                let paths = settings::paths(&self.config, key).unwrap_or_default();
+                entries.extend(paths.iter().map(|p| format!("{}{}", REMOVE_PATH, p.display())));
+                entries
+            }
+            Some(Screen::Editor(editor)) => editor.entries(),
+            _ => Vec::new(),
+        };
+
+        let query = if self.typing_value() { "" } else { self.input_value.trim() };
+        let matcher = self.matcher();
+        self.filtered_entries = entries
+            .into_iter()
+            .filter(|entry| query.is_empty() || matcher.fuzzy_match(entry, query).is_some())
+            .collect();
+        self.selected_index = self.selected_index.min(self.filtered_entries.len().saturating_sub(1));
+    }
+
+    fn push_screen(&mut self, screen: Screen) {
+        let lists = !matches!(screen, Screen::Input(_));
+        self.screens.push(screen);
+        self.input_value.clear();
+        self.status = None;
+        if lists {
+            self.show_screen();
+            self.selected_index = 0;
+            self.display_start_index = 0;
+        }
+        // Keep the current value selected so Enter alone keeps it
+        let current = match self.screens.last() {
+            Some(Screen::Terminals(_)) => Some(self.config.terminal.clone()),
+            Some(Screen::Choices(key, _)) => Some(settings::value(&self.config, key)),
+            _ => None,
+        };
+        if let Some(current) = current {
+            self.select_entry(|entry| entry == current);
+        }
+    }
+
+    // Leaving the editor this way drops its unsaved changes
+    fn pop_screen(&mut self) -> Command<Message> {
+        let Some(screen) = self.screens.pop() else {
+            return Command::none();
+        };
+        let command = match &screen {
+            Screen::Editor(editor) => self.set_theme(editor.original.clone()),
+            _ => Command::none(),
+        };
+        self.show_parent(&screen);
+        command
+    }
+
+    // Shows the screen below `child` again with the entry that opened `child` selected;
+    // below the first screen is the mode settings was opened from
+    fn show_parent(&mut self, child: &Screen) {
+        if self.screens.is_empty() {
+            self.set_mode(self.previous_mode);
+            return;
+        }
+        self.input_value.clear();
+        self.status = None;
+        self.show_screen();
+        self.selected_index = 0;
+        self.display_start_index = 0;
+        let origin = child.origin();
+        self.select_entry(|entry| entry == origin || settings::key_of(entry) == Some(origin));
+    }
+
+    // Escape, or Backspace on an empty input: cancels the field being edited, ends a theme
+    // preview, or goes up one screen
+    fn back(&mut self) -> Command<Message> {
+        if self.editor().is_some_and(|e| e.editing.is_some()) {
+            return self.cancel_editing();
+        }
+        if self.preview.is_some() {
+            self.revert_preview();
+            self.select_current_theme();
+            return Command::none();
+        }
+        self.pop_screen()
+    }
+
+    fn terminal_selection(&mut self, entry: &str) -> Command<Message> {
+        if entry == OTHER_TERMINAL {
+            self.push_screen(Screen::Input(SettingInput::Terminal));
+            self.input_value = self.config.terminal.clone();
+            return Command::none();
+        }
+        self.config.terminal = entry.to_string();
+        let command = self.pop_screen();
+        Command::batch([command, self.setting_changed("terminal")])
+    }
+
+    fn choice_selection(&mut self, key: &'static str, entry: &str) -> Command<Message> {
+        if let Err(e) = settings::set_value(&mut self.config, key, entry) {
+            self.status = Some(format!("{:#}", e));
+            return Command::none();
+        }
+        let command = self.pop_screen();
+        Command::batch([command, self.setting_changed(key)])
+    }
+
+    fn path_selection(&mut self, key: &'static str, entry: &str) -> Command<Message> {
+        if entry == ADD_PATH {
+            self.push_screen(Screen::Input(SettingInput::AddPath(key)));
+            return Command::none();
+        }
+        let Some(removed) = entry.strip_prefix(REMOVE_PATH) else {
+            return Command::none();
+        };
+        if let Some(paths) = settings::paths_mut(&mut self.config, key) {
+            paths.retain(|p| p.display().to_string() != removed);
+        }
+        let command = self.setting_changed(key);
+        self.refresh();
+        command
+    }
+
+    fn config_field_selection(&mut self, key: &'static str) -> Command<Message> {
+        match settings::kind(key) {
+            Some(settings::Kind::Bool) => {
+                settings::toggle(&mut self.config, key);
+                let command = self.setting_changed(key);
+                self.refresh();
+                command
+            }
+            Some(settings::Kind::Number | settings::Kind::Dimension | settings::Kind::Text) => {
+                self.push_screen(Screen::Input(SettingInput::Value(key)));
+                self.input_value = settings::value(&self.config, key);
+                Command::none()
+            }
+            Some(settings::Kind::Choice) => {
+                self.push_screen(Screen::Choices(key, settings::choices(key)));
+                Command::none()
+            }
+            Some(settings::Kind::Terminal) => {
+                self.push_screen(Screen::Terminals(settings::detect_terminals()));
+                Command::none()
+            }
+            Some(settings::Kind::Paths) => {
+                self.push_screen(Screen::Paths(key));
+                Command::none()
+            }
+            None => Command::none(),
+        }
+    }
+
+    fn submit_setting(&mut self) -> Command<Message> {
+        let Some(Screen::Input(input)) = self.screens.last() else {
+            return Command::none();
+        };
+        let input = *input;
+        let value = self.input_value.trim().to_string();
+        let (key, result) = match input {
+            SettingInput::Value(key) => (key, settings::set_value(&mut self.config, key, &value)),
+            SettingInput::AddPath(key) => {
+                let result = if value.is_empty() {
+                    Err(anyhow::anyhow!("enter a directory"))
+                } else {
+                    if let Some(paths) = settings::paths_mut(&mut self.config, key) {
+                        let path = PathBuf::from(&value);
+                        if !paths.contains(&path) {
+                            paths.push(path);
+                        }
+                    }
+                    Ok(())
+                };
+                (key, result)
+            }
+            SettingInput::Terminal => {
+                let result = if value.is_empty() {
+                    Err(anyhow::anyhow!("enter a terminal command"))
+                } else {
+                    self.config.terminal = value;
+                    Ok(())
+                };
+                ("terminal", result)
+            }
+        };
+        if let Err(e) = result {
+            self.status = Some(format!("{:#}", e));
+            return Command::none();
+        }
+
+        let mut screen = self.screens.pop();
+        // A typed terminal closes the terminal list along with the input
+        if input == SettingInput::Terminal {
+            screen = self.screens.pop();
+        }
+        if let Some(screen) = screen {
+            self.show_parent(&screen);
+        }
+        let command = self.setting_changed(key);
+        self.refresh();
+        command
+    }
+
+    // Saves the config and applies whatever the changed field affects right away
+    fn setting_changed(&mut self, key: &str) -> Command<Message> {
+        self.save_config(key);
+        match key {
+            "layout" | "width" | "height" | "location" | "x_offset" | "y_offset" | "monitor" | "fullscreen" | "auto_height" => {
+                self.place_window(false)
+            }
+            "search_paths" => {
+                self.entries = Self::load_applications(&self.config.search_paths).unwrap_or_default();
+                Command::none()
+            }
+            "icon_size" => {
+                self.icons = Icons::new(self.config.icon_size);
+                Command::none()
+            }
+            "theme_paths" => {
+                self.themes = AppTheme::get_available_themes(&self.config.theme_paths);
+                Command::none()
+            }
+            _ => Command::none(),
+        }
+    }
+
+    fn typing_value(&self) -> bool {
+        matches!(self.screens.last(), Some(Screen::Input(_))) || self.editor().is_some_and(|e| e.editing.is_some())
+    }
+
+    fn handle_settings_selection(&mut self, entry: &str) -> Command<Message> {
+        if entry == BACK_TO_MAIN || entry == BACK_TO_SETTINGS || entry == theme_editor::BACK {
+            return self.pop_screen();
+        }
+        match self.screens.last() {
+            Some(Screen::Editor(_)) => self.editor_selection(entry),
+            Some(Screen::Terminals(_)) => self.terminal_selection(entry),
+            Some(Screen::Choices(key, _)) => {
+                let key = *key;
+                self.choice_selection(key, entry)
+            }
+            Some(Screen::Paths(key)) => {
+                let key = *key;
+                self.path_selection(key, entry)
+            }
+            Some(Screen::Settings) => {
+                if let Some(key) = settings::key_of(entry) {
+                    self.config_field_selection(key)
+                } else if entry == EDIT_THEME {
+                    let editor = ThemeEditor::new(&self.theme);
+                    self.push_screen(Screen::Editor(Box::new(editor)));
+                    Command::none()
+                } else if let Some(name) = entry.strip_prefix("Theme: ") {
+                    let load_fonts = self.change_theme(name.to_string());
+                    self.save_config("theme");
+                    load_fonts
+                } else {
+                    Command::none()
+                }
+            }
+            _ => Command::none(),
+        }
+    }
+
+    fn editor_selection(&mut self, entry: &str) -> Command<Message> {
+        let Some(editor) = self.editor_mut() else {
+            return Command::none();
+        };
+        if entry == theme_editor::SAVE {
+            editor.editing = Some(Editing::SaveName);
+            self.input_value = format!("{}-custom", editor.theme.name);
+        } else if let Some(key) = ThemeEditor::key_of(entry) {
+            let key = key.to_string();
+            self.input_value = editor.begin(&key);
+        }
+        Command::none()
+    }
+
+    fn editor_input(&mut self, value: String) -> Command<Message> {
+        self.input_value = value;
+        let input = self.input_value.clone();
+        let Some(editor) = self.editor_mut() else {
+            return Command::none();
+        };
+        // Half-typed values are expected; errors are only reported on Enter
+        if matches!(editor.editing, Some(Editing::Field { .. })) && editor.input(&input).is_ok() {
+            let theme = editor.theme.clone();
+            self.show_screen();
+            self.status = None;
+            return self.set_theme(theme);
+        }
+        Command::none()
+    }
+
+    fn editor_submit(&mut self) -> Command<Message> {
+        let input = self.input_value.clone();
+        let theme_paths = self.config.theme_paths.clone();
+        let Some(editor) = self.editor_mut() else {
+            return Command::none();
+        };
+        match editor.editing {
+            Some(Editing::Field { .. }) => match editor.commit(&input) {
+                Ok(()) => {
+                    self.input_value.clear();
+                    self.show_screen();
+                    self.status = None;
+                }
+                Err(e) => self.status = Some(format!("{:#}", e)),
+            },
+            Some(Editing::SaveName) => match editor.save(&input, &theme_paths) {
+                Ok(path) => {
+                    self.themes = AppTheme::get_available_themes(&self.config.theme_paths);
+                    // The saved theme takes over from the one the editor started from
+                    if let Some(screen) = self.screens.pop() {
+                        self.show_parent(&screen);
+                    }
+                    let load_fonts = self.change_theme(input.trim().to_string());
+                    self.save_config("theme");
+                    self.refresh();
+                    if self.status.is_none() {
+                        self.status = Some(format!("Saved {}", path.display()));
+                    }
+                    return load_fonts;
+                }
+                Err(e) => self.status = Some(format!("{:#}", e)),
+            },
+            None => {}
+        }
+        Command::none()
+    }
+
+    // Restores the field being edited and goes back to the list of fields
+    fn cancel_editing(&mut self) -> Command<Message> {
+        let Some(editor) = self.editor_mut() else {
+            return Command::none();
+        };
+        editor.cancel();
+        let theme = editor.theme.clone();
+        self.input_value.clear();
+        self.show_screen();
+        self.status = None;
+        self.set_theme(theme)
+    }
+
+    fn execute_command(&self, cmd: &str) -> Result<()> {
+        ProcessCommand::new("sh")
+            .arg("-c")
+            .arg(cmd)
+            .spawn()?;
+        Ok(())
+    }
+
+    fn move_selection(&mut self, delta: isize) {
+        if !self.filtered_entries.is_empty() {
+            let len = self.filtered_entries.len();
+            self.selected_index = if delta < 0 && self.selected_index == 0 {
+                len - 1
+            } else if delta > 0 && self.selected_index == len - 1 {
+                0
+            } else {
+                let new_index = (self.selected_index as isize + delta) as usize;
+                if new_index < len {
+                    new_index
+                } else if delta > 0 {
+                    0
+                } else {
+                    len - 1
+                }
+            };
+
+            // Adjust display window to keep selected item visible, a whole row at a time in the grid
+            let (visible, step) = self.visible_entries();
+            let row_start = self.selected_index - self.selected_index % step;
+            if self.selected_index < self.display_start_index {
+                self.display_start_index = row_start;
+            } else if step > 1 {
+                if self.selected_index >= self.display_start_index + visible {
+                    self.display_start_index = (row_start + step).saturating_sub(visible);
+                }
+            } else {
+                // Section headers take lines too, so fewer results than `visible` may fit
+                self.display_start_index = self.display_start_index.max((self.selected_index + 1).saturating_sub(visible));
+                while self.display_start_index < self.selected_index
+                    && self.lines_between(self.display_start_index, self.selected_index + 1) > visible
+                {
+                    self.display_start_index += 1;
+                }
+            }
+        }
+    }
+
+    // Up and Down in the grid; they stop at the first and last rows instead of wrapping
+    fn move_row(&mut self, rows: isize) {
+        let columns = self.config.grid_columns.max(1);
+        let len = self.filtered_entries.len();
+        let target = self.selected_index as isize + rows * columns as isize;
+        if target >= 0 && (target as usize) < len {
+            self.move_selection(target - self.selected_index as isize);
+        } else if rows > 0 && self.selected_index / columns < len.saturating_sub(1) / columns {
+            // The row below is short; land on its last tile
+            self.move_selection(len as isize - 1 - self.selected_index as isize);
+        }
+    }
+
+    fn evaluate_math(&self, input: &str) -> Option<f64> {
+        let input = input.trim().replace(" ", "");
+        
+        // Simple math parser for basic operations
+        if let Some(pos) = input.find('+') {
+            let (left, right) = input.split_at(pos);
+            let right = &right[1..]; // skip the '+'
+            if let (Ok(a), Ok(b)) = (left.parse::<f64>(), right.parse::<f64>()) {
+                return Some(a + b);
+            }
+        }
+        
+        if let Some(pos) = input.find('-') {
+            if pos > 0 { // Don't treat negative numbers as subtraction
+                let (left, right) = input.split_at(pos);
+                let right = &right[1..]; // skip the '-'
+                if let (Ok(a), Ok(b)) = (left.parse::<f64>(), right.parse::<f64>()) {
+                    return Some(a - b);
+                }
+            }
+        }
+        
+        if let Some(pos) = input.find('*') {
+            let (left, right) = input.split_at(pos);
+            let right = &right[1..]; // skip the '*'
+            if let (Ok(a), Ok(b)) = (left.parse::<f64>(), right.parse::<f64>()) {
+                return Some(a * b);
+            }
         }
         
         if let Some(pos) = input.find('/') {
@@ -526,3 +2095,227 @@ impl MenuWindow {
         None
     }
 }
+
+// The results that fit in `available` on the page holding `selected`; pages fill up from
+// the first result, like dmenu's
+fn page(widths: impl Iterator<Item = f32>, available: f32, selected: usize) -> (usize, usize) {
+    let mut start = 0;
+    let mut used = 0.0;
+    let mut count = 0;
+    for (index, width) in widths.enumerate() {
+        if index > start && used + width > available {
+            if selected < index {
+                return (start, index);
+            }
+            start = index;
+            used = 0.0;
+        }
+        used += width;
+        count = index + 1;
+    }
+    (start, count)
+}
+
+// Shortens `text` to `chars` characters, marking the cut with an ellipsis
+fn truncate(text: &str, chars: usize) -> String {
+    if text.chars().count() <= chars {
+        return text.to_string();
+    }
+    let mut short: String = text.chars().take(chars.saturating_sub(1)).collect();
+    short.push('…');
+    short
+}
+
+// Rough width of a line of text, for laying out the bar before it is drawn
+fn text_width(text: &str, style: &ElementPalette, font_size: f32) -> f32 {
+    let size = style.text_size.unwrap_or(font_size);
+    text.chars().count() as f32 * size * CHAR_WIDTH + 2.0 * style.padding
+}
+
+// Height of a line of text in an element with this style
+fn line_height(style: &ElementPalette, font_size: f32) -> f32 {
+    style.text_size.unwrap_or(font_size) * LINE_HEIGHT + 2.0 * style.padding
+}
+
+pub fn load_theme(config: &Config, theme_override: Option<&str>) -> (AppTheme, Option<String>) {
+    let theme_name = theme_override.unwrap_or(&config.theme);
+    match AppTheme::load(theme_name, &config.theme_paths) {
+        Ok(theme) => (theme, None),
+        Err(e) => {
+            log::error!("Failed to load theme {}: {:#}", theme_name, e);
+            (AppTheme::default(), Some(format!("Error in theme {}: {:#}", theme_name, e)))
+        }
+    }
+}
+
+// The status line for config files or variables that were left out, or else for unknown
+// or ignored config keys, if there are any
+pub fn config_status(errors: &[String], warnings: &[String]) -> Option<String> {
+    if !errors.is_empty() {
+        return Some(format!("Error, skipped: {}", errors.join("; ")));
+    }
+    (!warnings.is_empty()).then(|| format!("Warning: {}", warnings.join("; ")))
+}
+
+// Rows of the list: `max_entries`, or with `auto_height` one per result within the line limits
+fn list_rows(config: &Config, results: usize) -> usize {
+    if !config.auto_height {
+        return config.max_entries;
+    }
+    // More rows than `max_entries` would only ever be empty
+    let most = config.max_lines.min(config.max_entries);
+    results.min(most).max(config.min_lines.min(most))
+}
+
+// Height of the list's container with this many rows
+fn list_height(config: &Config, palette: &Palette, rows: usize) -> f32 {
+    let rows = rows as f32;
+    rows * line_height(&palette.row, config.font_size as f32)
+        + (rows - 1.0).max(0.0) * palette.list.spacing
+        + 2.0 * palette.list.padding
+}
+
+// Height of the line with the prompt and the input
+fn search_height(palette: &Palette, font_size: f32) -> f32 {
+    line_height(&palette.input, font_size).max(line_height(&palette.prompt, font_size))
+}
+
+// Height of the message block with this many lines, with the space above it
+fn message_height(palette: &Palette, font_size: f32, lines: usize) -> f32 {
+    if lines == 0 {
+        return 0.0;
+    }
+    let text_size = palette.prompt.text_size.unwrap_or(font_size);
+    palette.window.spacing + lines as f32 * text_size * LINE_HEIGHT + 2.0 * palette.prompt.padding
+}
+
+// The window height the layout needs, when it does not use the configured one
+pub fn content_height(config: &Config, palette: &Palette, results: usize, extras: Extras) -> Option<u32> {
+    let font_size = config.font_size as f32;
+    match config.layout {
+        Layout::List if config.auto_height => {
+            let list = list_height(config, palette, list_rows(config, results));
+            let mut height = search_height(palette, font_size) + palette.window.spacing + list + 2.0 * palette.window.padding;
+            if extras.status {
+                height += palette.window.spacing + line_height(&palette.status_bar, font_size);
+            }
+            height += message_height(palette, font_size, extras.message_lines);
+            if extras.pane {
+                let text_size = palette.row.text_size.unwrap_or(font_size);
+                height += palette.window.spacing + PANE_LINES * text_size * LINE_HEIGHT + 2.0 * palette.list.padding;
+            }
+            Some(height.ceil() as u32)
+        }
+        Layout::List | Layout::Grid => None,
+        Layout::Bar => {
+            let row = line_height(&palette.row, font_size) + 2.0 * palette.list.padding;
+            let line = search_height(palette, font_size).max(row);
+            Some((line + 2.0 * palette.window.padding).ceil() as u32)
+        }
+    }
+}
+
+// `font_size` from the config applies unless the theme sets a size for this element
+fn styled_text<'a>(content: impl Into<std::borrow::Cow<'a, str>>, style: &ElementPalette, font_size: f32) -> Text<'a> {
+    Text::new(content)
+        .font(style.font)
+        .size(style.text_size.unwrap_or(font_size))
+        .style(TextTheme::Color(style.text))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bar_page(widths: &[f32], available: f32, selected: usize) -> (usize, usize) {
+        page(widths.iter().copied(), available, selected)
+    }
+
+    #[test]
+    fn pages_fill_up_from_the_first_result() {
+        let widths = [30.0; 5];
+        assert_eq!(bar_page(&widths, 100.0, 0), (0, 3));
+        assert_eq!(bar_page(&widths, 100.0, 2), (0, 3));
+        assert_eq!(bar_page(&widths, 100.0, 3), (3, 5));
+        assert_eq!(bar_page(&widths, 100.0, 4), (3, 5));
+    }
+
+    #[test]
+    fn pages_take_results_that_fit_exactly() {
+        assert_eq!(bar_page(&[50.0; 3], 100.0, 0), (0, 2));
+        assert_eq!(bar_page(&[50.0; 3], 100.0, 2), (2, 3));
+    }
+
+    #[test]
+    fn results_wider_than_the_bar_get_a_page_of_their_own() {
+        let widths = [150.0, 20.0, 20.0];
+        assert_eq!(bar_page(&widths, 100.0, 0), (0, 1));
+        assert_eq!(bar_page(&widths, 100.0, 1), (1, 3));
+    }
+
+    #[test]
+    fn no_results_make_an_empty_page() {
+        assert_eq!(bar_page(&[], 100.0, 0), (0, 0));
+    }
+
+    fn auto_height(min_lines: usize, max_lines: usize, max_entries: usize) -> Config {
+        Config { auto_height: true, min_lines, max_lines, max_entries, ..Config::default() }
+    }
+
+    fn height(config: &Config, results: usize, extras: Extras) -> u32 {
+        content_height(config, &Palette::from(&AppTheme::default()), results, extras).unwrap()
+    }
+
+    #[test]
+    fn list_rows_follow_the_results_within_the_limits() {
+        let config = auto_height(3, 10, 15);
+        assert_eq!(list_rows(&config, 0), 3);
+        assert_eq!(list_rows(&config, 5), 5);
+        assert_eq!(list_rows(&config, 40), 10);
+        // `max_entries` caps the rows too, and wins over `min_lines`
+        assert_eq!(list_rows(&auto_height(3, 10, 8), 40), 8);
+        assert_eq!(list_rows(&auto_height(12, 20, 8), 0), 8);
+    }
+
+    #[test]
+    fn list_rows_are_fixed_without_auto_height() {
+        let config = Config { max_entries: 7, ..Config::default() };
+        assert_eq!(list_rows(&config, 0), 7);
+        assert_eq!(list_rows(&config, 40), 7);
+    }
+
+    #[test]
+    fn auto_height_grows_by_a_row_at_a_time() {
+        let config = auto_height(1, 10, 15);
+        let none = Extras::default();
+        let step = height(&config, 3, none) - height(&config, 2, none);
+        assert!(step > 0);
+        assert!((height(&config, 5, none) as i64 - height(&config, 2, none) as i64 - 3 * step as i64).abs() <= 1);
+        // Held between `min_lines` and `max_lines`
+        assert_eq!(height(&config, 0, none), height(&config, 1, none));
+        assert_eq!(height(&config, 40, none), height(&config, 10, none));
+    }
+
+    #[test]
+    fn auto_height_makes_room_for_the_extras() {
+        let config = auto_height(1, 10, 15);
+        let plain = height(&config, 4, Extras::default());
+        assert!(height(&config, 4, Extras { status: true, ..Extras::default() }) > plain);
+        assert!(height(&config, 4, Extras { pane: true, ..Extras::default() }) > plain);
+        let one = height(&config, 4, Extras { message_lines: 1, ..Extras::default() });
+        let two = height(&config, 4, Extras { message_lines: 2, ..Extras::default() });
+        assert!(one > plain && two > one);
+    }
+
+    #[test]
+    fn only_auto_sized_lists_and_bars_set_their_height() {
+        let palette = Palette::from(&AppTheme::default());
+        let extras = Extras::default();
+        assert_eq!(content_height(&Config::default(), &palette, 4, extras), None);
+        let grid = Config { layout: Layout::Grid, auto_height: true, ..Config::default() };
+        assert_eq!(content_height(&grid, &palette, 4, extras), None);
+
+        let bar = Config { layout: Layout::Bar, ..Config::default() };
+        assert_eq!(height(&bar, 0, extras), height(&bar, 40, extras));
+    }
+}
@@ -0,0 +1,298 @@
+use anyhow::Result;
+use std::path::PathBuf;
+
+use crate::config::{Config, Dimension, Layout, Location, MonitorSelector, PreviewPosition, Source};
+use crate::placement;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    // Within `crate::config::range`
+    Number,
+    // Pixels within `config::range`, or a percentage of the monitor
+    Dimension,
+    Bool,
+    Choice,
+    Text,
+    Terminal,
+    Paths,
+}
+
+// Config fields editable from settings, in the order they are listed. `theme` has its own
+// rows; `config_version` and `include` describe the files rather than the launcher
+pub const FIELDS: &[(&str, Kind)] = &[
+    ("placeholder", Kind::Text),
+    ("message", Kind::Text),
+    ("prompts.apps", Kind::Text),
+    ("prompts.settings", Kind::Text),
+    ("prompts.dmenu", Kind::Text),
+    ("layout", Kind::Choice),
+    ("grid_columns", Kind::Number),
+    ("icon_size", Kind::Number),
+    ("preview", Kind::Choice),
+    ("preview_command", Kind::Text),
+    ("width", Kind::Dimension),
+    ("height", Kind::Dimension),
+    ("location", Kind::Choice),
+    ("x_offset", Kind::Number),
+    ("y_offset", Kind::Number),
+    ("monitor", Kind::Choice),
+    ("fullscreen", Kind::Bool),
+    ("font_size", Kind::Number),
+    ("max_entries", Kind::Number),
+    ("auto_height", Kind::Bool),
+    ("min_lines", Kind::Number),
+    ("max_lines", Kind::Number),
+    ("terminal", Kind::Terminal),
+    ("search_paths", Kind::Paths),
+    ("hide_on_lose_focus", Kind::Bool),
+    ("case_sensitive", Kind::Bool),
+    ("sources", Kind::Text),
+    ("group_results", Kind::Bool),
+    ("source_limits.calculator", Kind::Number),
+    ("source_limits.apps", Kind::Number),
+    ("source_limits.settings", Kind::Number),
+    ("theme_paths", Kind::Paths),
+];
+
+// Offered in the terminal picker when found on $PATH
+const TERMINALS: &[&str] = &[
+    "alacritty", "foot", "ghostty", "gnome-terminal", "kitty", "konsole", "lxterminal", "mate-terminal",
+    "qterminal", "st", "terminator", "terminology", "tilix", "urxvt", "wezterm", "xfce4-terminal", "xterm",
+];
+
+pub fn kind(key: &str) -> Option<Kind> {
+    FIELDS.iter().find(|(k, _)| *k == key).map(|(_, kind)| *kind)
+}
+
+// `font_size: 14` and the like, as listed in settings
+pub fn entry(config: &Config, key: &str) -> String {
+    let value = match paths(config, key) {
+        Some([]) => "(empty)".to_string(),
+        Some(paths) => paths.iter().map(|p| p.display().to_string()).collect::<Vec<_>>().join(", "),
+        None => match value(config, key) {
+            value if value.is_empty() => "(empty)".to_string(),
+            // A multiline message stays on one row
+            value => value.replace('\n', " "),
+        },
+    };
+    format!("{}: {}", key, value)
+}
+
+// The field an entry from `entry` belongs to
+pub fn key_of(entry: &str) -> Option<&'static str> {
+    let (key, _) = entry.split_once(": ")?;
+    FIELDS.iter().find(|(k, _)| *k == key).map(|(k, _)| *k)
+}
+
+// A single value as it is typed in or picked
+pub fn value(config: &Config, key: &str) -> String {
+    match key {
+        "placeholder" => config.placeholder.clone(),
+        "message" => config.message.clone(),
+        "prompts.apps" => config.prompts.apps.clone(),
+        "prompts.settings" => config.prompts.settings.clone(),
+        "prompts.dmenu" => config.prompts.dmenu.clone(),
+        "layout" => config.layout.name().to_string(),
+        "grid_columns" => config.grid_columns.to_string(),
+        "icon_size" => config.icon_size.to_string(),
+        "preview" => config.preview.name().to_string(),
+        "preview_command" => config.preview_command.clone(),
+        "sources" => config.sources.iter().map(|s| s.name()).collect::<Vec<_>>().join(", "),
+        "source_limits.calculator" => config.source_limits.calculator.to_string(),
+        "source_limits.apps" => config.source_limits.apps.to_string(),
+        "source_limits.settings" => config.source_limits.settings.to_string(),
+        "width" => config.width.to_string(),
+        "height" => config.height.to_string(),
+        "location" => config.location.name().to_string(),
+        "x_offset" => config.x_offset.to_string(),
+        "y_offset" => config.y_offset.to_string(),
+        "monitor" => config.monitor.to_string(),
+        "fullscreen" => config.fullscreen.to_string(),
+        "font_size" => config.font_size.to_string(),
+        "max_entries" => config.max_entries.to_string(),
+        "auto_height" => config.auto_height.to_string(),
+        "min_lines" => config.min_lines.to_string(),
+        "max_lines" => config.max_lines.to_string(),
+        "terminal" => config.terminal.clone(),
+        "hide_on_lose_focus" => config.hide_on_lose_focus.to_string(),
+        "case_sensitive" => config.case_sensitive.to_string(),
+        _ => String::new(),
+    }
+}
+
+pub fn set_value(config: &mut Config, key: &str, text: &str) -> Result<()> {
+    // Applied to a copy first, so a value that conflicts with another field changes nothing
+    let mut changed = config.clone();
+    apply(&mut changed, key, text)?;
+    changed.validate().map_err(|message| anyhow::anyhow!(message))?;
+    *config = changed;
+    Ok(())
+}
+
+fn apply(config: &mut Config, key: &str, text: &str) -> Result<()> {
+    let text = text.trim();
+    match kind(key) {
+        Some(Kind::Number) => {
+            let value: i32 = text
+                .parse()
+                .map_err(|_| anyhow::anyhow!("`{}` is not a whole number", text))?;
+            let (min, max) = crate::config::range(key).unwrap_or((i64::MIN, i64::MAX));
+            if !(min..=max).contains(&i64::from(value)) {
+                anyhow::bail!("{} must be between {} and {}", key, min, max);
+            }
+            match key {
+                "grid_columns" => config.grid_columns = value as usize,
+                "icon_size" => config.icon_size = value as u16,
+                "x_offset" => config.x_offset = value,
+                "y_offset" => config.y_offset = value,
+                "font_size" => config.font_size = value as u16,
+                "max_entries" => config.max_entries = value as usize,
+                "min_lines" => config.min_lines = value as usize,
+                "max_lines" => config.max_lines = value as usize,
+                "source_limits.calculator" => config.source_limits.calculator = value as usize,
+                "source_limits.apps" => config.source_limits.apps = value as usize,
+                "source_limits.settings" => config.source_limits.settings = value as usize,
+                _ => {}
+            }
+        }
+        Some(Kind::Dimension) => {
+            let value: Dimension = text.parse().map_err(|e: String| anyhow::anyhow!(e))?;
+            if let (Dimension::Pixels(pixels), Some((min, max))) = (value, crate::config::range(key)) {
+                if !(min..=max).contains(&i64::from(pixels)) {
+                    anyhow::bail!("{} must be between {} and {} pixels, or a percentage", key, min, max);
+                }
+            }
+            match key {
+                "width" => config.width = value,
+                "height" => config.height = value,
+                _ => {}
+            }
+        }
+        Some(Kind::Choice) => match key {
+            "layout" => {
+                config.layout = Layout::ALL
+                    .into_iter()
+                    .find(|l| l.name() == text)
+                    .ok_or_else(|| anyhow::anyhow!("unknown layout `{}`", text))?;
+            }
+            "location" => {
+                config.location = Location::ALL
+                    .into_iter()
+                    .find(|l| l.name() == text)
+                    .ok_or_else(|| anyhow::anyhow!("unknown location `{}`", text))?;
+            }
+            "monitor" => config.monitor = MonitorSelector::Name(text.to_string()),
+            "preview" => {
+                config.preview = PreviewPosition::ALL
+                    .into_iter()
+                    .find(|p| p.name() == text)
+                    .ok_or_else(|| anyhow::anyhow!("unknown preview position `{}`", text))?;
+            }
+            _ => {}
+        },
+        Some(Kind::Text) => match key {
+            "placeholder" => config.placeholder = text.to_string(),
+            "message" => config.message = text.to_string(),
+            "prompts.apps" => config.prompts.apps = text.to_string(),
+            "prompts.settings" => config.prompts.settings = text.to_string(),
+            "prompts.dmenu" => config.prompts.dmenu = text.to_string(),
+            "preview_command" => config.preview_command = text.to_string(),
+            // `calculator, apps`: the sources to use, in order
+            "sources" => {
+                config.sources = text
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|name| !name.is_empty())
+                    .map(|name| {
+                        Source::ALL
+                            .into_iter()
+                            .find(|s| s.name() == name)
+                            .ok_or_else(|| anyhow::anyhow!("unknown source `{}`", name))
+                    })
+                    .collect::<Result<_>>()?;
+            }
+            _ => {}
+        },
+        _ => anyhow::bail!("{} cannot be typed in", key),
+    }
+    Ok(())
+}
+
+// What a choice field offers, in order
+pub fn choices(key: &str) -> Vec<String> {
+    match key {
+        "layout" => Layout::ALL.iter().map(|l| l.name().to_string()).collect(),
+        "location" => Location::ALL.iter().map(|l| l.name().to_string()).collect(),
+        "preview" => PreviewPosition::ALL.iter().map(|p| p.name().to_string()).collect(),
+        "monitor" => ["primary", "focused"]
+            .into_iter()
+            .map(str::to_string)
+            .chain(placement::monitors().into_iter().map(|m| m.name))
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+pub fn toggle(config: &mut Config, key: &str) {
+    match key {
+        "hide_on_lose_focus" => config.hide_on_lose_focus = !config.hide_on_lose_focus,
+        "case_sensitive" => config.case_sensitive = !config.case_sensitive,
+        "fullscreen" => config.fullscreen = !config.fullscreen,
+        "auto_height" => config.auto_height = !config.auto_height,
+        "group_results" => config.group_results = !config.group_results,
+        _ => {}
+    }
+}
+
+pub fn paths<'a>(config: &'a Config, key: &str) -> Option<&'a [PathBuf]> {
+    match key {
+        "search_paths" => Some(&config.search_paths),
+        "theme_paths" => Some(&config.theme_paths),
+        _ => None,
+    }
+}
+
+pub fn paths_mut<'a>(config: &'a mut Config, key: &str) -> Option<&'a mut Vec<PathBuf>> {
+    match key {
+        "search_paths" => Some(&mut config.search_paths),
+        "theme_paths" => Some(&mut config.theme_paths),
+        _ => None,
+    }
+}
+
+pub fn detect_terminals() -> Vec<String> {
+    let path = std::env::var_os("PATH").unwrap_or_default();
+    let dirs: Vec<PathBuf> = std::env::split_paths(&path).collect();
+    TERMINALS
+        .iter()
+        .filter(|name| dirs.iter().any(|dir| dir.join(name).is_file()))
+        .map(|name| name.to_string())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_config_key_has_a_row() {
+        let table = toml::Table::try_from(Config::default()).unwrap();
+        for (key, _) in crate::config::flatten(&table) {
+            if !matches!(key.as_str(), "theme" | "config_version" | "include") {
+                assert!(kind(&key).is_some(), "no settings row for `{}`", key);
+            }
+        }
+    }
+
+    #[test]
+    fn line_limits_are_checked_against_each_other() {
+        let mut config = Config::default();
+        assert!(set_value(&mut config, "min_lines", "20").is_err());
+        assert_eq!(config.min_lines, Config::default().min_lines);
+
+        set_value(&mut config, "max_lines", "30").unwrap();
+        set_value(&mut config, "min_lines", "20").unwrap();
+        assert!(set_value(&mut config, "max_lines", "10").is_err());
+        assert_eq!((config.min_lines, config.max_lines), (20, 30));
+    }
+}
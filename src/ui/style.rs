@@ -0,0 +1,107 @@
+use iced::{
+    widget::{container, text_input},
+    Background, Color, Theme,
+};
+use crate::themes::ElementPalette;
+
+// Custom styles for our UI elements
+#[derive(Debug, Clone, Copy)]
+pub struct DarkContainer {
+    pub bg_color: Color,
+    pub text_color: Color,
+    pub border_color: Option<Color>,
+    pub border_width: f32,
+    pub border_radius: f32,
+}
+
+impl DarkContainer {
+    pub fn new(style: &ElementPalette) -> Self {
+        Self {
+            bg_color: style.background,
+            text_color: style.text,
+            border_color: Some(style.border),
+            border_width: style.border_width,
+            border_radius: style.border_radius,
+        }
+    }
+}
+
+impl container::StyleSheet for DarkContainer {
+    type Style = Theme;
+
+    fn appearance(&self, _style: &Self::Style) -> container::Appearance {
+        container::Appearance {
+            text_color: Some(self.text_color),
+            background: Some(Background::Color(self.bg_color)),
+            border_radius: self.border_radius.into(),
+            border_width: self.border_width,
+            border_color: self.border_color.unwrap_or(self.bg_color),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct DarkTextInput {
+    pub bg_color: Color,
+    pub text_color: Color,
+    pub border_color: Color,
+    pub border_radius: f32,
+    pub border_width: f32,
+}
+
+impl DarkTextInput {
+    pub fn new(style: &ElementPalette) -> Self {
+        Self {
+            bg_color: style.background,
+            text_color: style.text,
+            border_color: style.border,
+            border_radius: style.border_radius,
+            border_width: style.border_width,
+        }
+    }
+}
+
+impl text_input::StyleSheet for DarkTextInput {
+    type Style = Theme;
+
+    fn active(&self, _style: &Self::Style) -> text_input::Appearance {
+        text_input::Appearance {
+            background: Background::Color(self.bg_color),
+            border_radius: self.border_radius.into(),
+            border_width: self.border_width,
+            border_color: self.border_color,
+            icon_color: self.text_color,
+        }
+    }
+
+    fn focused(&self, style: &Self::Style) -> text_input::Appearance {
+        let active = self.active(style);
+        text_input::Appearance {
+            border_color: self.text_color,
+            ..active
+        }
+    }
+
+    fn placeholder_color(&self, _style: &Self::Style) -> Color {
+        Color {
+            a: 0.5,
+            ..self.text_color
+        }
+    }
+
+    fn value_color(&self, _style: &Self::Style) -> Color {
+        self.text_color
+    }
+
+    fn selection_color(&self, _style: &Self::Style) -> Color {
+        Color { a: 0.2, ..self.text_color }
+    }
+
+    fn disabled(&self, style: &Self::Style) -> text_input::Appearance {
+        self.active(style)
+    }
+
+    fn disabled_color(&self, _style: &Self::Style) -> Color {
+        Color { a: 0.5, ..self.text_color }
+    }
+}
@@ -0,0 +1,252 @@
+use anyhow::Result;
+use iced::Color;
+use std::path::PathBuf;
+
+use crate::config::{insert, lookup};
+use crate::themes::{Theme, ThemeColor};
+
+pub const BACK: &str = "Back to Settings";
+pub const SAVE: &str = "Save as New Theme";
+
+const SECTIONS: &[&str] = &[
+    "window", "input", "prompt", "list", "row", "selected_row", "alternate_row", "status_bar",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Color,
+    Number,
+    Text,
+    Weight,
+}
+
+const WEIGHTS: &[&str] = &[
+    "thin", "extra-light", "light", "normal", "medium", "semibold", "bold", "extra-bold", "black",
+];
+
+pub enum Editing {
+    // Previous value of the field, restored when the edit is cancelled
+    Field { key: String, previous: Option<toml::Value> },
+    SaveName,
+}
+
+// A working copy of a theme, edited one field at a time and previewed as it changes
+pub struct ThemeEditor {
+    pub original: Theme,
+    // Last state of the working copy that deserialized into a valid theme
+    pub theme: Theme,
+    table: toml::Table,
+    pub editing: Option<Editing>,
+}
+
+impl ThemeEditor {
+    pub fn new(theme: &Theme) -> Self {
+        let table = match toml::Value::try_from(theme) {
+            Ok(toml::Value::Table(table)) => table,
+            _ => toml::Table::new(),
+        };
+        Self {
+            original: theme.clone(),
+            theme: theme.clone(),
+            table,
+            editing: None,
+        }
+    }
+
+    // Every editable field with its dotted key, flat fields first, then each section
+    fn fields() -> Vec<(String, Kind)> {
+        let mut fields: Vec<(String, Kind)> = [
+            ("name", Kind::Text),
+            ("background_color", Kind::Color),
+            ("text_color", Kind::Color),
+            ("selected_background_color", Kind::Color),
+            ("selected_text_color", Kind::Color),
+            ("border_color", Kind::Color),
+            ("border_width", Kind::Number),
+            ("border_radius", Kind::Number),
+            ("padding", Kind::Number),
+        ]
+        .iter()
+        .map(|(key, kind)| (key.to_string(), *kind))
+        .collect();
+
+        let section_fields = [
+            ("background", Kind::Color),
+            ("text_color", Kind::Color),
+            ("border_color", Kind::Color),
+            ("border_width", Kind::Number),
+            ("border_radius", Kind::Number),
+            ("padding", Kind::Number),
+            ("spacing", Kind::Number),
+            ("font.family", Kind::Text),
+            ("font.size", Kind::Number),
+            ("font.weight", Kind::Weight),
+        ];
+        for section in SECTIONS {
+            for (field, kind) in section_fields {
+                fields.push((format!("{}.{}", section, field), kind));
+            }
+        }
+        fields
+    }
+
+    fn kind(key: &str) -> Option<Kind> {
+        Self::fields().into_iter().find(|(k, _)| k == key).map(|(_, kind)| kind)
+    }
+
+    pub fn entries(&self) -> Vec<String> {
+        let mut entries = vec![BACK.to_string()];
+        for (key, _) in Self::fields() {
+            let value = self.value(&key).unwrap_or_else(|| "(inherited)".to_string());
+            entries.push(format!("{}: {}", key, value));
+        }
+        entries.push(SAVE.to_string());
+        entries
+    }
+
+    // The field an entry from `entries` belongs to
+    pub fn key_of(entry: &str) -> Option<&str> {
+        let (key, _) = entry.split_once(": ")?;
+        Self::kind(key).map(|_| key)
+    }
+
+    // `row.font.size` and the like
+    fn get(&self, key: &str) -> Option<&toml::Value> {
+        lookup(&self.table, &key.split('.').collect::<Vec<_>>())
+    }
+
+    pub fn value(&self, key: &str) -> Option<String> {
+        match self.get(key)? {
+            toml::Value::String(s) => Some(s.clone()),
+            toml::Value::Float(f) => Some(f.to_string()),
+            toml::Value::Integer(i) => Some(i.to_string()),
+            other => Some(other.to_string()),
+        }
+    }
+
+    // Swatch shown next to color fields; tracks the input while a color is being edited
+    pub fn swatch(&self, entry: &str) -> Option<Color> {
+        let key = Self::key_of(entry)?;
+        if Self::kind(key) != Some(Kind::Color) {
+            return None;
+        }
+        self.value(key)?.parse::<ThemeColor>().ok().map(|c| c.color())
+    }
+
+    pub fn begin(&mut self, key: &str) -> String {
+        let previous = self.get(key).cloned();
+        self.editing = Some(Editing::Field { key: key.to_string(), previous });
+        self.value(key).unwrap_or_default()
+    }
+
+    pub fn hint(&self, key: &str) -> &'static str {
+        match Self::kind(key) {
+            Some(Kind::Color) => "#RRGGBB, rgb(), hsl() or a color name; empty to inherit",
+            Some(Kind::Number) => "a number; empty to inherit",
+            Some(Kind::Weight) => "thin, light, normal, medium, semibold, bold, black…",
+            _ => "text; empty to inherit",
+        }
+    }
+
+    // Applies the input to the field being edited if it makes a valid theme
+    pub fn input(&mut self, text: &str) -> Result<()> {
+        let Some(Editing::Field { key, .. }) = &self.editing else {
+            return Ok(());
+        };
+        let key = key.clone();
+        let kind = Self::kind(&key).unwrap_or(Kind::Text);
+        let text = text.trim();
+
+        // Sections fall back to the flat fields, but the flat fields themselves are required
+        let value = if text.is_empty() && !key.contains('.') {
+            anyhow::bail!("{} cannot be empty", key);
+        } else if text.is_empty() {
+            None
+        } else {
+            Some(match kind {
+                Kind::Color => {
+                    text.parse::<ThemeColor>()?;
+                    toml::Value::String(text.to_string())
+                }
+                Kind::Number => {
+                    let number: f64 = text.parse().map_err(|_| anyhow::anyhow!("`{}` is not a number", text))?;
+                    if number < 0.0 {
+                        anyhow::bail!("{} cannot be negative", key);
+                    }
+                    toml::Value::Float(number)
+                }
+                Kind::Weight => {
+                    if !WEIGHTS.contains(&text) {
+                        anyhow::bail!("unknown font weight `{}`", text);
+                    }
+                    toml::Value::String(text.to_string())
+                }
+                Kind::Text => toml::Value::String(text.to_string()),
+            })
+        };
+        self.set(&key, value)
+    }
+
+    // Keeps the field as it is now; fails while the input is still invalid
+    pub fn commit(&mut self, text: &str) -> Result<()> {
+        self.input(text)?;
+        self.editing = None;
+        Ok(())
+    }
+
+    pub fn cancel(&mut self) {
+        if let Some(Editing::Field { key, previous }) = self.editing.take() {
+            let _ = self.set(&key, previous);
+        }
+    }
+
+    fn set(&mut self, key: &str, value: Option<toml::Value>) -> Result<()> {
+        let mut table = self.table.clone();
+        let path: Vec<&str> = key.split('.').collect();
+        match value {
+            Some(value) => insert(&mut table, &path, value),
+            None => remove(&mut table, &path),
+        }
+        let theme: Theme = toml::Value::Table(table.clone())
+            .try_into()
+            .map_err(|e: toml::de::Error| anyhow::anyhow!("{}", e.message()))?;
+        self.table = table;
+        self.theme = theme;
+        Ok(())
+    }
+
+    // Writes the working copy as a standalone theme; existing themes are never overwritten
+    pub fn save(&mut self, name: &str, theme_paths: &[PathBuf]) -> Result<PathBuf> {
+        let name = name.trim();
+        if name.is_empty() || Theme::is_path(name) {
+            anyhow::bail!("theme names cannot be empty or contain '/'");
+        }
+        if Theme::get_available_themes(theme_paths).iter().any(|t| t == name) {
+            anyhow::bail!("a theme named {} already exists", name);
+        }
+
+        let mut table = self.table.clone();
+        table.insert("name".to_string(), toml::Value::String(name.to_string()));
+        // Every field is already resolved, so the file does not depend on its parent
+        table.remove("extends");
+        let path = Theme::dir().join(format!("{}.toml", name));
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&path, toml::to_string_pretty(&table)?)?;
+        self.editing = None;
+        Ok(path)
+    }
+}
+
+fn remove(table: &mut toml::Table, path: &[&str]) {
+    let (last, parents) = path.split_last().expect("keys are not empty");
+    let mut table = table;
+    for key in parents {
+        match table.get_mut(*key).and_then(toml::Value::as_table_mut) {
+            Some(next) => table = next,
+            None => return,
+        }
+    }
+    table.remove(*last);
+}
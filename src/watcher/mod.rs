@@ -0,0 +1,47 @@
+use std::path::PathBuf;
+use std::time::Duration;
+use iced::{subscription, Subscription};
+use iced::futures::SinkExt;
+use notify::Watcher;
+
+pub use notify::RecursiveMode;
+
+// Editors usually touch a file several times per save, so wait for things to settle
+const DEBOUNCE: Duration = Duration::from_millis(200);
+
+// Emits once for every burst of changes to files with `extension` in `dir`, and with
+// `RecursiveMode::Recursive` in the directories below it
+pub fn watch(dir: PathBuf, extension: impl Into<String>, mode: RecursiveMode) -> Subscription<()> {
+    struct ConfigWatcher;
+
+    let extension = extension.into();
+    let id = (std::any::TypeId::of::<ConfigWatcher>(), dir.clone(), extension.clone(), mode);
+    subscription::channel(id, 1, move |mut output| async move {
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+
+        let watcher = notify::recommended_watcher(move |res: notify::Result<notify::Event>| {
+            if let Ok(event) = res {
+                let matches = event.paths.iter().any(|p| p.extension().is_some_and(|e| *e == *extension));
+                if matches && !event.kind.is_access() {
+                    let _ = tx.send(());
+                }
+            }
+        })
+        .and_then(|mut watcher| watcher.watch(&dir, mode).map(|_| watcher));
+
+        let _watcher = match watcher {
+            Ok(watcher) => watcher,
+            Err(e) => {
+                log::error!("Failed to watch {}: {}", dir.display(), e);
+                return iced::futures::future::pending().await;
+            }
+        };
+
+        while rx.recv().await.is_some() {
+            tokio::time::sleep(DEBOUNCE).await;
+            while rx.try_recv().is_ok() {}
+            let _ = output.send(()).await;
+        }
+        iced::futures::future::pending().await
+    })
+}